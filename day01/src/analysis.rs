@@ -0,0 +1,265 @@
+// Landing and pass counts for every position on the dial, or for a chosen set of targets.
+
+use crate::dial::{self, CountOverflow, Dial, Step};
+
+/// How often a position was landed on and passed during a rotation sequence.
+///
+/// A pass is any click that reaches the position, including the final click of a
+/// rotation that lands on it, which is the part 2 rule applied to that position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PositionCount {
+    pub position: i64,
+    pub landings: u64,
+    pub passes: u64,
+}
+
+/// Counts landings and passes for every position in `0..modulus`.
+///
+/// Each step covers some whole revolutions plus one contiguous arc of clicks, so the passes
+/// are accumulated in a difference array: O(steps + modulus) time and O(modulus) memory.
+/// Landings of a repeated step cost O(min(repeat, modulus)) since they cycle.
+pub fn histogram(mut dial: Dial, steps: &[Step]) -> Result<Vec<PositionCount>, CountOverflow> {
+    let modulus = dial.modulus();
+    let size = modulus as usize;
+    let mut landings = vec![0u64; size];
+    // diff[i] - diff[i - 1] is the number of partial arcs that start covering position i
+    let mut diff = vec![0i64; size + 1];
+    let mut full_revolutions: u64 = 0;
+
+    for &step in steps {
+        let before = dial.position();
+        let turn = step.total();
+        let distance = turn.unsigned_abs();
+        full_revolutions = dial::add_count(full_revolutions, distance / modulus as u64)?;
+        let remainder = (distance % modulus as u64) as i64;
+        if remainder > 0 {
+            // The arc covers before+1..=before+r to the right, or before-r..=before-1 to the left
+            let first = if turn > 0 {
+                (before + 1).rem_euclid(modulus)
+            } else {
+                (before - remainder).rem_euclid(modulus)
+            };
+            add_arc(&mut diff, first as usize, remainder as usize);
+        }
+        for (position, count) in dial::step_landings(before, step, modulus) {
+            landings[position as usize] = dial::add_count(landings[position as usize], count)?;
+        }
+        dial.rotate_step(step);
+    }
+
+    let mut covered = 0i64;
+    (0..size)
+        .map(|position| {
+            covered += diff[position];
+            Ok(PositionCount {
+                position: position as i64,
+                landings: landings[position],
+                passes: dial::add_count(full_revolutions, covered as u64)?,
+            })
+        })
+        .collect()
+}
+
+// Adds one pass to `len` consecutive positions starting at `first`, wrapping around the dial.
+fn add_arc(diff: &mut [i64], first: usize, len: usize) {
+    let size = diff.len() - 1;
+    let end = first + len;
+    diff[first] += 1;
+    if end <= size {
+        diff[end] -= 1;
+    } else {
+        diff[size] -= 1;
+        diff[0] += 1;
+        diff[end - size] -= 1;
+    }
+}
+
+/// Counts landings and passes for each of `targets`, in the order given.
+///
+/// Targets are wrapped into `0..modulus`. Costs O(steps × targets) and no per-position memory,
+/// so it suits huge dials where only a few positions matter.
+pub fn target_counts(
+    mut dial: Dial,
+    steps: &[Step],
+    targets: &[i64],
+) -> Result<Vec<PositionCount>, CountOverflow> {
+    let modulus = dial.modulus();
+    let mut counts: Vec<PositionCount> = targets
+        .iter()
+        .map(|&target| PositionCount {
+            position: target.rem_euclid(modulus),
+            landings: 0,
+            passes: 0,
+        })
+        .collect();
+
+    for &step in steps {
+        let before = dial.position();
+        dial.rotate_step(step);
+        for count in &mut counts {
+            let passes = dial::target_crossings(before, step.total(), modulus, count.position);
+            let landings = dial::target_landings(before, step, modulus, count.position);
+            count.passes = dial::add_count(count.passes, passes)?;
+            count.landings = dial::add_count(count.landings, landings)?;
+        }
+    }
+    Ok(counts)
+}
+
+/// Both passwords for one starting position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartResult {
+    pub start: i64,
+    pub part1: u64,
+    pub part2: u64,
+}
+
+/// Evaluates both passwords for every starting position in `0..modulus`.
+///
+/// Starting at `s` instead of 0 shifts every position by `s`, so reaching 0 from `s` is the
+/// same as reaching `-s` from 0. One histogram from 0 therefore answers all starts at once.
+pub fn sweep(modulus: i64, steps: &[Step]) -> Result<Vec<StartResult>, CountOverflow> {
+    let counts = histogram(Dial::new(modulus, 0), steps)?;
+    Ok((0..modulus)
+        .map(|start| {
+            let count = counts[(-start).rem_euclid(modulus) as usize];
+            StartResult {
+                start,
+                part1: count.landings,
+                part2: count.passes,
+            }
+        })
+        .collect())
+}
+
+/// Starts giving the largest and smallest value of `password`, preferring the lowest start on ties.
+pub fn best_and_worst(
+    results: &[StartResult],
+    password: impl Fn(&StartResult) -> u64,
+) -> Option<(StartResult, StartResult)> {
+    let best = results
+        .iter()
+        .min_by_key(|result| (std::cmp::Reverse(password(result)), result.start))?;
+    let worst = results
+        .iter()
+        .min_by_key(|result| (password(result), result.start))?;
+    Some((*best, *worst))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dial::test_support::{EXAMPLE, expand, once, overflowing_landings};
+
+    #[test]
+    fn test_zero_matches_both_parts() {
+        let counts = histogram(Dial::new(100, 50), &once(&EXAMPLE)).unwrap();
+        assert_eq!(counts.len(), 100);
+        assert_eq!(counts[0].landings, 3);
+        assert_eq!(counts[0].passes, 6);
+        assert_eq!(counts.iter().map(|c| c.landings).sum::<u64>(), 10);
+    }
+
+    #[test]
+    fn test_histogram_matches_target_counts() {
+        let turns = [
+            -68, -30, 48, -5, 60, -55, -1, -99, 14, -82, 0, 700, -701, 13, -13, 250,
+        ];
+        for modulus in [1, 3, 7, 100] {
+            let dial = Dial::new(modulus, 50);
+            let targets: Vec<i64> = (0..modulus).collect();
+            assert_eq!(
+                histogram(dial, &once(&turns)).unwrap(),
+                target_counts(dial, &once(&turns), &targets).unwrap(),
+                "modulus {modulus}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_sweep_matches_simulation() {
+        let turns = [-68, -30, 48, -5, 60, -55, -1, -99, 14, -82, 300, -301];
+        for modulus in [1, 4, 100] {
+            let results = sweep(modulus, &once(&turns)).unwrap();
+            assert_eq!(results.len(), modulus as usize);
+            for result in results {
+                let mut dial = Dial::new(modulus, result.start);
+                let (mut part1, mut part2) = (0, 0);
+                for &turn in &turns {
+                    let rotation = dial.rotate(turn);
+                    part1 += u64::from(rotation.position == 0);
+                    part2 += rotation.crossings;
+                }
+                assert_eq!((result.part1, result.part2), (part1, part2));
+            }
+        }
+    }
+
+    #[test]
+    fn test_best_and_worst() {
+        let results = sweep(100, &once(&EXAMPLE)).unwrap();
+        assert_eq!(results[50].part1, 3);
+        let (best, worst) = best_and_worst(&results, |result| result.part1).unwrap();
+        assert!(
+            results
+                .iter()
+                .all(|r| worst.part1 <= r.part1 && r.part1 <= best.part1)
+        );
+        assert!(
+            results
+                .iter()
+                .all(|r| r.part1 < best.part1 || r.start >= best.start)
+        );
+        assert_eq!(best_and_worst(&[], |result| result.part2), None);
+    }
+
+    #[test]
+    fn test_repeated_steps_match_expansion() {
+        let steps = [
+            Step::new(25, 9).unwrap(),
+            Step::new(-7, 40).unwrap(),
+            Step::new(0, 5).unwrap(),
+            Step::new(100, 3).unwrap(),
+            Step::new(-150, 2).unwrap(),
+        ];
+        let expanded = expand(&steps);
+        for modulus in [1, 10, 100] {
+            let dial = Dial::new(modulus, 3);
+            let targets: Vec<i64> = (0..modulus).collect();
+            let counts = histogram(dial, &steps).unwrap();
+            assert_eq!(
+                counts,
+                histogram(dial, &expanded).unwrap(),
+                "modulus {modulus}"
+            );
+            assert_eq!(
+                counts,
+                target_counts(dial, &steps, &targets).unwrap(),
+                "modulus {modulus}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_overflowing_counts_are_errors() {
+        let steps = overflowing_landings();
+        let dial = Dial::new(100, 50);
+        assert_eq!(histogram(dial, &steps[..3]).unwrap()[0].landings, u64::MAX);
+        assert_eq!(histogram(dial, &steps), Err(CountOverflow));
+        assert_eq!(sweep(100, &steps), Err(CountOverflow));
+        assert_eq!(target_counts(dial, &steps, &[0]), Err(CountOverflow));
+        // Passes overflow too, once enough long turns have gone round
+        let far = vec![Step::once(i64::MAX); 250];
+        assert_eq!(histogram(dial, &far), Err(CountOverflow));
+        assert_eq!(target_counts(dial, &far, &[7]), Err(CountOverflow));
+    }
+
+    #[test]
+    fn test_targets_are_wrapped() {
+        let counts = target_counts(Dial::new(100, 50), &once(&EXAMPLE), &[-100, 199]).unwrap();
+        assert_eq!(counts[0].position, 0);
+        assert_eq!((counts[0].landings, counts[0].passes), (3, 6));
+        assert_eq!(counts[1].position, 99);
+        assert_eq!(counts[1].landings, 1);
+    }
+}
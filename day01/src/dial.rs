@@ -0,0 +1,415 @@
+// A safe dial with numbers 0 through modulus - 1, shared by both parts.
+
+use std::error::Error;
+use std::fmt;
+
+/// Outcome of a single rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rotation {
+    /// Position the dial points at after the rotation.
+    pub position: i64,
+    /// Number of clicks during the rotation that landed on 0.
+    pub crossings: u64,
+}
+
+/// Outcome of a repeated rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepRotation {
+    /// Position the dial points at after the last repetition.
+    pub position: i64,
+    /// Number of repetitions that left the dial at 0.
+    pub landings: u64,
+    /// Number of clicks across all repetitions that landed on 0.
+    pub crossings: u64,
+}
+
+/// A turn applied `repeat` times in a row, such as `R10x500`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    turn: i64,
+    repeat: u64,
+}
+
+impl Step {
+    /// A step repeated at least once whose combined distance fits in an `i64`, so it can be
+    /// applied in one go.
+    pub fn new(turn: i64, repeat: u64) -> Option<Self> {
+        if repeat == 0 {
+            return None;
+        }
+        i64::try_from(repeat).ok()?.checked_mul(turn)?;
+        Some(Step { turn, repeat })
+    }
+
+    /// A single rotation.
+    pub fn once(turn: i64) -> Self {
+        Step { turn, repeat: 1 }
+    }
+
+    pub fn turn(&self) -> i64 {
+        self.turn
+    }
+
+    pub fn repeat(&self) -> u64 {
+        self.repeat
+    }
+
+    /// The combined turn of every repetition; the clicks are the same as turning once this far.
+    pub fn total(&self) -> i64 {
+        self.turn * self.repeat as i64
+    }
+}
+
+/// A count of rotations, landings or clicks that does not fit in a `u64`.
+///
+/// A single step stays below `i64::MAX` clicks, but repeated steps can add up past `u64::MAX`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountOverflow;
+
+impl fmt::Display for CountOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the repeated rotations add up to a count too large for a u64"
+        )
+    }
+}
+
+impl Error for CountOverflow {}
+
+/// `total + count`, or [`CountOverflow`] if that does not fit.
+pub fn add_count(total: u64, count: u64) -> Result<u64, CountOverflow> {
+    total.checked_add(count).ok_or(CountOverflow)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dial {
+    modulus: i64,
+    position: i64,
+}
+
+impl Dial {
+    /// Creates a dial with `modulus` numbers pointing at `start`.
+    ///
+    /// `start` is wrapped into `0..modulus`, so `Dial::new(100, -1)` points at 99.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `modulus` is not positive.
+    pub fn new(modulus: i64, start: i64) -> Self {
+        assert!(modulus > 0, "dial modulus must be positive, got {modulus}");
+        Dial {
+            modulus,
+            position: start.rem_euclid(modulus),
+        }
+    }
+
+    /// How many numbers are around the dial.
+    pub fn modulus(&self) -> i64 {
+        self.modulus
+    }
+
+    /// Number the dial currently points at.
+    pub fn position(&self) -> i64 {
+        self.position
+    }
+
+    /// Turns the dial by `turn` clicks (positive is right, negative is left).
+    pub fn rotate(&mut self, turn: i64) -> Rotation {
+        let crossings = zero_crossings(self.position, turn, self.modulus);
+        self.position = advance(self.position, turn, self.modulus);
+        Rotation {
+            position: self.position,
+            crossings,
+        }
+    }
+
+    /// Applies every repetition of `step` without replaying them one by one.
+    pub fn rotate_step(&mut self, step: Step) -> StepRotation {
+        let landings = target_landings(self.position, step, self.modulus, 0);
+        let rotation = self.rotate(step.total());
+        StepRotation {
+            position: rotation.position,
+            landings,
+            crossings: rotation.crossings,
+        }
+    }
+}
+
+// Position after turning, computed wide so turns near i64::MAX cannot overflow.
+fn advance(position: i64, turn: i64, modulus: i64) -> i64 {
+    (position as i128 + turn as i128).rem_euclid(modulus as i128) as i64
+}
+
+/// Number of clicks that land on 0 when turning a dial at `position` by `turn`.
+pub fn zero_crossings(position: i64, turn: i64, modulus: i64) -> u64 {
+    let (position, turn, modulus) = (position as i128, turn as i128, modulus as i128);
+    let num_crossings = if turn >= 0 {
+        (position + turn).div_euclid(modulus)
+    } else {
+        (position - 1).div_euclid(modulus) - (position + turn - 1).div_euclid(modulus)
+    };
+    num_crossings.unsigned_abs() as u64
+}
+
+/// Number of clicks that land on `target` when turning a dial at `position` by `turn`.
+///
+/// This is the zero-crossing rule applied to a dial relabelled so that `target` reads 0.
+pub fn target_crossings(position: i64, turn: i64, modulus: i64, target: i64) -> u64 {
+    zero_crossings((position - target).rem_euclid(modulus), turn, modulus)
+}
+
+/// Number of repetitions of `step`, starting at `position`, that leave the dial at `target`.
+///
+/// After k repetitions the dial is at `position + k * turn`, so this counts the solutions of
+/// `k * turn ≡ target - position (mod modulus)` with `1 <= k <= repeat`. They form one residue
+/// class modulo `modulus / gcd(turn, modulus)`, which makes the count O(log modulus).
+pub fn target_landings(position: i64, step: Step, modulus: i64, target: i64) -> u64 {
+    let modulus = modulus as i128;
+    let turn = (step.turn as i128).rem_euclid(modulus);
+    let offset = (target as i128 - position as i128).rem_euclid(modulus);
+
+    let (divisor, inverse, _) = extended_gcd(turn, modulus);
+    if offset % divisor != 0 {
+        return 0;
+    }
+    // Every repetition count in k0 + period * Z lands on the target
+    let period = modulus / divisor;
+    let k0 = ((offset / divisor) * inverse).rem_euclid(period);
+    let first = if k0 == 0 { period } else { k0 };
+    let repeat = step.repeat as i128;
+    if first > repeat {
+        0
+    } else {
+        ((repeat - first) / period + 1) as u64
+    }
+}
+
+/// Positions visited by the first `min(repeat, cycle)` repetitions of `step`, with how many of
+/// the `repeat` repetitions end on each. Later repetitions only revisit the same cycle.
+pub fn step_landings(position: i64, step: Step, modulus: i64) -> impl Iterator<Item = (i64, u64)> {
+    let turn = step.turn.rem_euclid(modulus);
+    let (divisor, _, _) = extended_gcd(turn as i128, modulus as i128);
+    let cycle = (modulus as i128 / divisor) as u64;
+    let mut current = position;
+    (1..=step.repeat.min(cycle)).map(move |k| {
+        current = advance(current, turn, modulus);
+        (current, (step.repeat - k) / cycle + 1)
+    })
+}
+
+// Returns (g, x, y) with a * x + b * y = g = gcd(a, b), treating gcd(0, b) as b.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if a == 0 {
+        (b, 0, 1)
+    } else {
+        let (g, x, y) = extended_gcd(b % a, a);
+        (g, y - (b / a) * x, x)
+    }
+}
+
+// Turn lists shared by the tests of the modules built on the dial.
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::Step;
+
+    /// The rotations of the puzzle's example, with right turns positive.
+    pub const EXAMPLE: [i64; 10] = [-68, -30, 48, -5, 60, -55, -1, -99, 14, -82];
+
+    /// Each turn as a single rotation.
+    pub fn once(turns: &[i64]) -> Vec<Step> {
+        turns.iter().map(|&turn| Step::once(turn)).collect()
+    }
+
+    /// Two repeats of nearly `i64::MAX` rotations that all land where the first turn did, with
+    /// a single landing before and after, so the landings and rotations come to `u64::MAX + 1`.
+    pub fn overflowing_landings() -> Vec<Step> {
+        let stay = Step::new(0, i64::MAX as u64).unwrap();
+        vec![Step::once(-50), stay, stay, Step::once(0)]
+    }
+
+    /// Every repetition of `steps` as a rotation of its own.
+    pub fn expand(steps: &[Step]) -> Vec<Step> {
+        steps
+            .iter()
+            .flat_map(|step| std::iter::repeat_n(Step::once(step.turn()), step.repeat() as usize))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    // Reference model: move the dial one click at a time and count every click that reaches 0.
+    fn simulate_clicks(position: i64, turn: i64, modulus: i64) -> (i64, u64) {
+        let step = turn.signum();
+        let mut position = position;
+        let mut crossings = 0;
+        for _ in 0..turn.unsigned_abs() {
+            position = (position + step).rem_euclid(modulus);
+            if position == 0 {
+                crossings += 1;
+            }
+        }
+        (position, crossings)
+    }
+
+    fn random_turn(rng: &mut Rng, modulus: i64) -> i64 {
+        let distance = match rng.below(4) {
+            0 => rng.below(modulus as u64 + 1),
+            1 => modulus as u64 * rng.below(5),
+            2 => rng.below(20 * modulus as u64),
+            _ => rng.below(200_000),
+        } as i64;
+        if rng.below(2) == 0 {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    fn assert_matches_reference(modulus: i64, start: i64, turns: &[i64]) {
+        let mut dial = Dial::new(modulus, start);
+        let mut position = start;
+        for (index, &turn) in turns.iter().enumerate() {
+            let (expected_position, expected_crossings) = simulate_clicks(position, turn, modulus);
+            let rotation = dial.rotate(turn);
+            assert_eq!(
+                rotation,
+                Rotation {
+                    position: expected_position,
+                    crossings: expected_crossings,
+                },
+                "modulus {modulus}, start {start}, turn #{index} = {turn} from {position}"
+            );
+            position = expected_position;
+        }
+    }
+
+    #[test]
+    fn test_closed_form_matches_reference_on_random_sequences() {
+        let mut rng = Rng::new(2025);
+        for modulus in [1, 2, 3, 7, 10, 100] {
+            for _ in 0..25 {
+                let start = rng.below(modulus as u64) as i64;
+                let turns: Vec<i64> = (0..20).map(|_| random_turn(&mut rng, modulus)).collect();
+                assert_matches_reference(modulus, start, &turns);
+            }
+        }
+    }
+
+    #[test]
+    fn test_closed_form_matches_reference_on_edge_cases() {
+        for modulus in [1, 5, 100] {
+            for start in 0..modulus {
+                let turns: Vec<i64> = (-3..=3)
+                    .flat_map(|revolutions| {
+                        [
+                            revolutions * modulus - 1,
+                            revolutions * modulus,
+                            revolutions * modulus + 1,
+                        ]
+                    })
+                    .collect();
+                assert_matches_reference(modulus, start, &turns);
+            }
+        }
+
+        // Left turns that start on 0 must not count the starting position
+        assert_matches_reference(100, 0, &[-1, -99, -100, -100, -250, 0, -5_000_000]);
+        assert_matches_reference(100, 0, &[100, -100, 3_000_000, -3_000_001, 1]);
+    }
+
+    #[test]
+    fn test_rotate_step_matches_repeated_rotations() {
+        let mut rng = Rng::new(7);
+        for modulus in [1, 2, 6, 12, 100] {
+            for _ in 0..200 {
+                let start = rng.below(modulus as u64) as i64;
+                let turn = random_turn(&mut rng, modulus) % 1000;
+                let step = Step::new(turn, 1 + rng.below(299)).unwrap();
+
+                let mut repeated = Dial::new(modulus, start);
+                let mut expected = StepRotation {
+                    position: start,
+                    landings: 0,
+                    crossings: 0,
+                };
+                let mut visits = std::collections::HashMap::new();
+                for _ in 0..step.repeat() {
+                    let rotation = repeated.rotate(turn);
+                    expected.position = rotation.position;
+                    expected.landings += u64::from(rotation.position == 0);
+                    expected.crossings += rotation.crossings;
+                    *visits.entry(rotation.position).or_insert(0) += 1;
+                }
+
+                let mut dial = Dial::new(modulus, start);
+                assert_eq!(
+                    dial.rotate_step(step),
+                    expected,
+                    "{step:?} from {start} mod {modulus}"
+                );
+                let mut landings: Vec<(i64, u64)> = step_landings(start, step, modulus).collect();
+                landings.sort_unstable();
+                let mut visits: Vec<(i64, u64)> = visits.into_iter().collect();
+                visits.sort_unstable();
+                assert_eq!(landings, visits);
+            }
+        }
+    }
+
+    #[test]
+    fn test_step_bounds() {
+        assert_eq!(
+            Step::new(i64::MAX, 1).map(|step| step.total()),
+            Some(i64::MAX)
+        );
+        assert_eq!(Step::new(2, 1 << 62), None);
+        assert_eq!(Step::new(-3, u64::MAX), None);
+        assert_eq!(Step::new(5, 0), None);
+        let mut dial = Dial::new(100, 99);
+        assert_eq!(
+            dial.rotate(i64::MAX).position,
+            (99 + i64::MAX as i128).rem_euclid(100) as i64
+        );
+    }
+
+    #[test]
+    fn test_example_rotations() {
+        let mut dial = Dial::new(100, 50);
+        let mut landings = 0;
+        let mut crossings = 0;
+        for turn in test_support::EXAMPLE {
+            let rotation = dial.rotate(turn);
+            if rotation.position == 0 {
+                landings += 1;
+            }
+            crossings += rotation.crossings;
+        }
+        assert_eq!(landings, 3);
+        assert_eq!(crossings, 6);
+        assert_eq!(dial.rotate(0).position, 32);
+    }
+
+    #[test]
+    fn test_small_modulus() {
+        let mut dial = Dial::new(7, -1);
+        assert_eq!(
+            dial.rotate(1),
+            Rotation {
+                position: 0,
+                crossings: 1
+            }
+        );
+        assert_eq!(
+            dial.rotate(-15),
+            Rotation {
+                position: 6,
+                crossings: 2
+            }
+        );
+    }
+}
@@ -56,55 +56,240 @@
 //
 // Analyze the rotations in your attached document. What's the actual password to open the door?
 
-use std::fs;
+mod analysis;
+mod dial;
+mod parse;
+mod prefix;
+mod rng;
+mod synth;
+mod trace;
+
+use dial::{CountOverflow, Dial, Step};
+use prefix::PrefixIndex;
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use trace::TraceFormat;
 
 const MODULUS: i64 = 100;
+const START: i64 = 50;
+
+const USAGE: &str = "usage: day01 [INPUT]\n       day01 trace <csv|jsonl> [INPUT]\n       day01 histogram [INPUT]\n       day01 sweep [INPUT]\n       day01 range <FROM..TO[,FROM..TO...]> [INPUT]\n       day01 generate <PART1> <PART2> <LENGTH> [SEED]\n       day01 targets <P,P,...> [INPUT]\n\nINPUT defaults to input.txt; use - for stdin";
 
 fn main() {
-    // Part 1
-    println!("Part 1: {}", solve_part1("input.txt"));
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let input = |index: usize| args.get(index).map_or("input.txt", String::as_str);
+
+    match args.first().map(String::as_str) {
+        Some("-h" | "--help") => println!("{USAGE}"),
+        Some("trace") => {
+            // One CSV or JSON Lines record per rotation, streamed to stdout
+            let format: TraceFormat = args.get(1).ok_or(USAGE)?.parse()?;
+            let instructions = parse::instructions(open_input(input(2))?);
+            let records = trace::trace(Dial::new(MODULUS, START), instructions);
+            trace::write_trace(&mut io::stdout().lock(), format, records)?;
+        }
+        Some("histogram") => {
+            let steps = load_input(input(1))?;
+            print_counts(&analysis::histogram(Dial::new(MODULUS, START), &steps)?);
+        }
+        Some("sweep") => {
+            let steps = load_input(input(1))?;
+            print_sweep(&analysis::sweep(MODULUS, &steps)?);
+        }
+        Some("range") => {
+            // Rotation indices are 0-based and TO is exclusive, matching the trace index column
+            let ranges = args
+                .get(1)
+                .ok_or(USAGE)?
+                .split(',')
+                .map(parse_range)
+                .collect::<Result<Vec<_>, _>>()?;
+            let steps = load_input(input(2))?;
+            let index = PrefixIndex::new(Dial::new(MODULUS, START), &steps)?;
+            for range in ranges {
+                let summary = index.query(range.clone()).ok_or_else(|| {
+                    format!(
+                        "range {range:?} is not within the {} rotations",
+                        index.rotations()
+                    )
+                })?;
+                println!(
+                    "{range:?}: start {}, end {}, Part 1 {}, Part 2 {}",
+                    summary.start_position,
+                    summary.end_position,
+                    summary.landings,
+                    summary.crossings
+                );
+            }
+        }
+        Some("generate") => {
+            // Writes a rotation document with the requested passwords to stdout
+            let number = |index: usize, name: &str| -> Result<u64, String> {
+                let text = args.get(index).ok_or(USAGE)?;
+                text.parse()
+                    .map_err(|_| format!("invalid {name} {text:?}, expected a number"))
+            };
+            let target = synth::Target {
+                part1: number(1, "part 1 password")?,
+                part2: number(2, "part 2 password")?,
+                length: number(3, "length")? as usize,
+            };
+            let seed = if args.len() > 4 {
+                number(4, "seed")?
+            } else {
+                0
+            };
+            let turns = synth::synthesize(Dial::new(MODULUS, START), target, seed)?;
+            print!("{}", synth::format_rotations(&turns));
+        }
+        Some("targets") => {
+            let targets = args
+                .get(1)
+                .ok_or(USAGE)?
+                .split(',')
+                .map(|target| target.trim().parse::<i64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| format!("invalid target position: {err}"))?;
+            let steps = load_input(input(2))?;
+            let dial = Dial::new(MODULUS, START);
+            print_counts(&analysis::target_counts(dial, &steps, &targets)?);
+        }
+        _ => {
+            let steps = load_input(input(0))?;
+
+            // Part 1
+            println!("Part 1: {}", solve_part1(&steps)?);
+
+            // Part 2
+            println!("Part 2: {}", solve_part2(&steps)?);
+        }
+    }
+    Ok(())
+}
+
+fn open_input(path: &str) -> Result<Box<dyn BufRead>, Box<dyn Error>> {
+    if path == "-" {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    let file = File::open(path).map_err(|err| format!("cannot open {path}: {err}"))?;
+    Ok(Box::new(BufReader::new(file)))
+}
 
-    // Part 2
-    println!("Part 2: {}", solve_part2("input.txt"));
+fn load_input(path: &str) -> Result<Vec<Step>, Box<dyn Error>> {
+    let steps = parse::steps(open_input(path)?).collect::<Result<Vec<_>, _>>()?;
+    Ok(steps)
 }
 
-fn load_input(path: &str) -> Vec<i64> {
-    let input: String = fs::read_to_string(path).expect("Cannot find path");
-    let directions: String = input.replace("R", "").replace("L", "-");
-    let turns: Vec<i64> = directions
-        .split_whitespace()
-        .filter_map(|x| x.parse().ok())
-        .collect();
-    turns
+fn parse_range(text: &str) -> Result<std::ops::Range<u64>, String> {
+    let invalid = || format!("invalid range {text:?}, expected FROM..TO");
+    let (from, to) = text.trim().split_once("..").ok_or_else(invalid)?;
+    let from = from.parse().map_err(|_| invalid())?;
+    let to = to.parse().map_err(|_| invalid())?;
+    Ok(from..to)
 }
 
-fn solve_part1(input: &str) -> u64 {
-    let mut dial: i64 = 50;
+fn print_counts(counts: &[analysis::PositionCount]) {
+    println!("{:>8} {:>10} {:>10}", "position", "landings", "passes");
+    for count in counts {
+        println!(
+            "{:>8} {:>10} {:>10}",
+            count.position, count.landings, count.passes
+        );
+    }
+}
+
+fn print_sweep(results: &[analysis::StartResult]) {
+    println!("{:>5} {:>10} {:>10}", "start", "part1", "part2");
+    for result in results {
+        println!(
+            "{:>5} {:>10} {:>10}",
+            result.start, result.part1, result.part2
+        );
+    }
+
+    print_extremes("Part 1", results, |result| result.part1);
+    print_extremes("Part 2", results, |result| result.part2);
+}
+
+fn print_extremes(
+    name: &str,
+    results: &[analysis::StartResult],
+    password: fn(&analysis::StartResult) -> u64,
+) {
+    if let Some((best, worst)) = analysis::best_and_worst(results, password) {
+        println!(
+            "{name}: best start {} ({}), worst start {} ({})",
+            best.start,
+            password(&best),
+            worst.start,
+            password(&worst)
+        );
+    }
+}
+
+fn solve_part1(steps: &[Step]) -> Result<u64, CountOverflow> {
+    let mut dial = Dial::new(MODULUS, START);
     let mut num_zeroes: u64 = 0;
 
-    let turns: Vec<i64> = load_input(input);
-    for turn in turns {
-        dial = (dial + turn).rem_euclid(MODULUS);
-        if dial == 0 {
-            num_zeroes += 1;
-        }
+    // Repeated steps are counted arithmetically rather than replayed
+    for &step in steps {
+        num_zeroes = dial::add_count(num_zeroes, dial.rotate_step(step).landings)?;
     }
-    num_zeroes
+    Ok(num_zeroes)
 }
 
-fn solve_part2(input: &str) -> u64 {
-    let mut dial: i64 = 50;
+fn solve_part2(steps: &[Step]) -> Result<u64, CountOverflow> {
+    let mut dial = Dial::new(MODULUS, START);
     let mut password: u64 = 0;
 
-    let turns: Vec<i64> = load_input(input);
-    for turn in turns {
-        let num_crossings = if turn >= 0 {
-            (dial + turn).div_euclid(MODULUS)
-        } else {
-            (dial - 1).div_euclid(MODULUS) - (dial + turn - 1).div_euclid(MODULUS)
-        };
-        dial = (dial + turn).rem_euclid(MODULUS);
-        password += num_crossings.unsigned_abs();
+    for &step in steps {
+        password = dial::add_count(password, dial.rotate_step(step).crossings)?;
+    }
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "L68\nL30\nR48\nL5\nR60\nL55\nL1\nL99\nR14\nL82\n";
+
+    #[test]
+    fn test_example() {
+        let steps: Vec<Step> = parse::steps(EXAMPLE.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(solve_part1(&steps), Ok(3));
+        assert_eq!(solve_part2(&steps), Ok(6));
+    }
+
+    #[test]
+    fn test_compressed_example() {
+        let compressed = "# example with repeats\nL68, L30\nR48,L5 ,R60\nL55\nL1\nL99\nR14\nL82\nR50x2000000000\n";
+        let steps: Vec<Step> = parse::steps(compressed.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        // The dial ends at 32, so R50 alternates between 82 and 32 and never lands on 0
+        assert_eq!(solve_part1(&steps), Ok(3));
+        assert_eq!(solve_part2(&steps), Ok(6 + 1_000_000_000));
+    }
+
+    #[test]
+    fn test_overflowing_counts_are_errors() {
+        let steps = dial::test_support::overflowing_landings();
+        assert_eq!(solve_part1(&steps[..3]), Ok(u64::MAX));
+        assert_eq!(solve_part1(&steps), Err(CountOverflow));
+        // Each of these clicks past 0 about i64::MAX / 100 times
+        let far = vec![Step::once(i64::MAX); 250];
+        assert_eq!(solve_part2(&far), Err(CountOverflow));
     }
-    password
 }
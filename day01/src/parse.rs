@@ -0,0 +1,261 @@
+// Streaming parser for rotation documents.
+//
+// The plain puzzle format is one `L<n>` or `R<n>` per line. Generated documents may also use
+// repeat counts (`R10x500`), several comma-separated instructions per line and `#` comments.
+
+use crate::dial::Step;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    InvalidRotation {
+        line: usize,
+        text: String,
+        reason: RotationError,
+    },
+}
+
+/// Why an instruction is not a rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationError {
+    /// Not `L<n>` or `R<n>` with an optional `x<count>`.
+    Syntax,
+    /// An `x0` suffix, which would not rotate at all.
+    ZeroRepeat,
+    /// A distance, repeat count or their product that does not fit in an `i64`.
+    TooFar,
+}
+
+impl fmt::Display for RotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RotationError::Syntax => write!(f, "expected L<n> or R<n>, optionally with x<count>"),
+            RotationError::ZeroRepeat => write!(f, "the repeat count must be at least 1"),
+            RotationError::TooFar => write!(f, "the total distance does not fit in an i64"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(err) => write!(f, "failed to read rotations: {err}"),
+            ParseError::InvalidRotation { line, text, reason } => {
+                write!(f, "line {line}: invalid rotation {text:?}: {reason}")
+            }
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(err) => Some(err),
+            ParseError::InvalidRotation { .. } => None,
+        }
+    }
+}
+
+/// A single instruction of a rotation document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// 1-based line number in the document.
+    pub line: usize,
+    /// The instruction as written, without surrounding whitespace.
+    pub text: String,
+    /// Signed turn (right turns are positive) and how often it is repeated.
+    pub step: Step,
+}
+
+/// Iterator over the instructions in a rotation document, read line by line.
+///
+/// Blank lines and comments are skipped.
+pub struct Instructions<R> {
+    lines: io::Lines<R>,
+    line: usize,
+    pending: VecDeque<Instruction>,
+}
+
+pub fn instructions<R: BufRead>(reader: R) -> Instructions<R> {
+    Instructions {
+        lines: reader.lines(),
+        line: 0,
+        pending: VecDeque::new(),
+    }
+}
+
+/// Steps in a rotation document, without the source text.
+pub fn steps<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Step, ParseError>> {
+    instructions(reader).map(|instruction| instruction.map(|instruction| instruction.step))
+}
+
+impl<R: BufRead> Iterator for Instructions<R> {
+    type Item = Result<Instruction, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let text = match self.lines.next()? {
+                Ok(text) => text,
+                Err(err) => return Some(Err(ParseError::Io(err))),
+            };
+            self.line += 1;
+
+            let content = text.split('#').next().unwrap_or_default().trim();
+            if content.is_empty() {
+                continue;
+            }
+            for token in content.split(',') {
+                let token = token.trim();
+                let step = match parse_rotation(token) {
+                    Ok(step) => step,
+                    Err(reason) => {
+                        self.pending.clear();
+                        return Some(Err(ParseError::InvalidRotation {
+                            line: self.line,
+                            text: token.to_string(),
+                            reason,
+                        }));
+                    }
+                };
+                self.pending.push_back(Instruction {
+                    line: self.line,
+                    text: token.to_string(),
+                    step,
+                });
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+/// Parses a single `L<n>` or `R<n>` instruction, with an optional `x<count>` repeat suffix.
+///
+/// The combined distance of a repeated instruction must fit in an `i64`.
+pub fn parse_rotation(text: &str) -> Result<Step, RotationError> {
+    let (sign, rest) = match text.split_at_checked(1) {
+        Some(("L", rest)) => (-1, rest),
+        Some(("R", rest)) => (1, rest),
+        _ => return Err(RotationError::Syntax),
+    };
+    match rest.split_once('x') {
+        Some((distance, repeat)) => {
+            let distance = parse_number::<i64>(distance)?;
+            let repeat = parse_number::<u64>(repeat)?;
+            if repeat == 0 {
+                return Err(RotationError::ZeroRepeat);
+            }
+            Step::new(sign * distance, repeat).ok_or(RotationError::TooFar)
+        }
+        None => Ok(Step::once(sign * parse_number::<i64>(rest)?)),
+    }
+}
+
+// Plain decimal digits only, so signs and whitespace inside an instruction are rejected.
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, RotationError> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(RotationError::Syntax);
+    }
+    text.parse().map_err(|_| RotationError::TooFar)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rotation() {
+        assert_eq!(parse_rotation("R48"), Ok(Step::once(48)));
+        assert_eq!(parse_rotation("L68"), Ok(Step::once(-68)));
+        assert_eq!(parse_rotation("L0"), Ok(Step::once(0)));
+        assert_eq!(parse_rotation("X12"), Err(RotationError::Syntax));
+        assert_eq!(parse_rotation("L1O"), Err(RotationError::Syntax));
+        assert_eq!(parse_rotation("R+5"), Err(RotationError::Syntax));
+        assert_eq!(parse_rotation("R"), Err(RotationError::Syntax));
+        assert_eq!(
+            parse_rotation("R99999999999999999999"),
+            Err(RotationError::TooFar)
+        );
+    }
+
+    #[test]
+    fn test_reports_line_number() {
+        let input = "L68\n\nR48\nL1O\nR5\n";
+        let parsed: Vec<_> = steps(input.as_bytes()).collect();
+        assert_eq!(parsed.len(), 4);
+        assert_eq!(parsed[0].as_ref().unwrap(), &Step::once(-68));
+        assert_eq!(parsed[1].as_ref().unwrap(), &Step::once(48));
+        match &parsed[2] {
+            Err(ParseError::InvalidRotation { line, text, .. }) => {
+                assert_eq!(*line, 4);
+                assert_eq!(text, "L1O");
+            }
+            other => panic!("expected invalid rotation, got {other:?}"),
+        }
+        // This is what the command line prints
+        assert_eq!(
+            parsed[2].as_ref().unwrap_err().to_string(),
+            "line 4: invalid rotation \"L1O\": expected L<n> or R<n>, optionally with x<count>"
+        );
+    }
+
+    #[test]
+    fn test_extended_syntax() {
+        assert_eq!(parse_rotation("R10x500").ok(), Step::new(10, 500));
+        assert_eq!(parse_rotation("L3x1"), Ok(Step::once(-3)));
+        assert_eq!(parse_rotation("R10x0"), Err(RotationError::ZeroRepeat));
+        assert_eq!(parse_rotation("R10x"), Err(RotationError::Syntax));
+        assert_eq!(parse_rotation("Rx5"), Err(RotationError::Syntax));
+        assert_eq!(parse_rotation("R1x2x3"), Err(RotationError::Syntax));
+        assert_eq!(
+            parse_rotation("R4611686018427387904x2"),
+            Err(RotationError::TooFar)
+        );
+        assert_eq!(
+            parse_rotation("R5x99999999999999999999"),
+            Err(RotationError::TooFar)
+        );
+
+        let error = steps("R10x0\n".as_bytes()).next().unwrap().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1: invalid rotation \"R10x0\": the repeat count must be at least 1"
+        );
+        let error = steps("L1\nR5x99999999999999999999".as_bytes())
+            .nth(1)
+            .unwrap();
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "line 2: invalid rotation \"R5x99999999999999999999\": the total distance does not fit in an i64"
+        );
+
+        let input = "# generated\nL68, R48x3 # tail\n\n  R5  \nL1,,R2\n";
+        let parsed: Vec<_> = instructions(input.as_bytes()).collect();
+        let ok: Vec<(usize, &str, Step)> = parsed[..3]
+            .iter()
+            .map(|instruction| {
+                let instruction = instruction.as_ref().unwrap();
+                (
+                    instruction.line,
+                    instruction.text.as_str(),
+                    instruction.step,
+                )
+            })
+            .collect();
+        assert_eq!(
+            ok,
+            [
+                (2, "L68", Step::once(-68)),
+                (2, "R48x3", Step::new(48, 3).unwrap()),
+                (4, "R5", Step::once(5)),
+            ]
+        );
+        assert!(matches!(
+            &parsed[3],
+            Err(ParseError::InvalidRotation { line: 5, text, .. }) if text.is_empty()
+        ));
+    }
+}
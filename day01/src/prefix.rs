@@ -0,0 +1,191 @@
+// Prefix sums over a rotation sequence, for counting zeroes inside any sub-range.
+
+use crate::dial::{self, CountOverflow, Dial, Step};
+use std::ops::Range;
+
+/// What happened during a contiguous run of rotations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeSummary {
+    /// Position of the dial before the first rotation of the range.
+    pub start_position: i64,
+    /// Position of the dial after the last rotation of the range.
+    pub end_position: i64,
+    /// Rotations in the range that left the dial at 0 (the part 1 rule).
+    pub landings: u64,
+    /// Clicks in the range that reached 0 (the part 2 rule).
+    pub crossings: u64,
+}
+
+pub struct PrefixIndex {
+    modulus: i64,
+    steps: Vec<Step>,
+    // Entry k describes the state after the first k steps, so each vector has one more entry
+    // than `steps`. `rotations` counts every repetition of a step separately.
+    rotations: Vec<u64>,
+    positions: Vec<i64>,
+    landings: Vec<u64>,
+    crossings: Vec<u64>,
+}
+
+// State of the dial after some number of rotations.
+struct Prefix {
+    position: i64,
+    landings: u64,
+    crossings: u64,
+}
+
+impl PrefixIndex {
+    /// Replays `steps` once from `dial`, recording the state after every step.
+    pub fn new(mut dial: Dial, steps: &[Step]) -> Result<Self, CountOverflow> {
+        let mut rotations = Vec::with_capacity(steps.len() + 1);
+        let mut positions = Vec::with_capacity(steps.len() + 1);
+        let mut landings = Vec::with_capacity(steps.len() + 1);
+        let mut crossings = Vec::with_capacity(steps.len() + 1);
+        rotations.push(0);
+        positions.push(dial.position());
+        landings.push(0);
+        crossings.push(0);
+
+        for &step in steps {
+            let rotation = dial.rotate_step(step);
+            rotations.push(dial::add_count(*rotations.last().unwrap(), step.repeat())?);
+            positions.push(rotation.position);
+            landings.push(dial::add_count(
+                *landings.last().unwrap(),
+                rotation.landings,
+            )?);
+            crossings.push(dial::add_count(
+                *crossings.last().unwrap(),
+                rotation.crossings,
+            )?);
+        }
+
+        Ok(PrefixIndex {
+            modulus: dial.modulus(),
+            steps: steps.to_vec(),
+            rotations,
+            positions,
+            landings,
+            crossings,
+        })
+    }
+
+    /// Number of rotations in the indexed sequence, counting each repetition separately.
+    pub fn rotations(&self) -> u64 {
+        *self.rotations.last().unwrap()
+    }
+
+    /// Summarises rotations `range.start..range.end` (0-based, end exclusive).
+    ///
+    /// The dial starts the range wherever the full sequence had left it. Returns `None` if
+    /// the range is reversed or extends past the last rotation. Takes O(log steps), or O(1)
+    /// when every step is a single rotation.
+    pub fn query(&self, range: Range<u64>) -> Option<RangeSummary> {
+        if range.start > range.end || range.end > self.rotations() {
+            return None;
+        }
+        let start = self.prefix(range.start);
+        let end = self.prefix(range.end);
+        Some(RangeSummary {
+            start_position: start.position,
+            end_position: end.position,
+            landings: end.landings - start.landings,
+            crossings: end.crossings - start.crossings,
+        })
+    }
+
+    // State after the first `rotations` rotations, finishing a partly applied step if needed.
+    fn prefix(&self, rotations: u64) -> Prefix {
+        let index = if self.steps.len() as u64 == self.rotations() {
+            rotations as usize
+        } else {
+            self.rotations.partition_point(|&count| count <= rotations) - 1
+        };
+        let mut prefix = Prefix {
+            position: self.positions[index],
+            landings: self.landings[index],
+            crossings: self.crossings[index],
+        };
+        let partial = rotations - self.rotations[index];
+        if partial > 0 {
+            let step = Step::new(self.steps[index].turn(), partial).unwrap();
+            let rotation = Dial::new(self.modulus, prefix.position).rotate_step(step);
+            prefix.position = rotation.position;
+            prefix.landings += rotation.landings;
+            prefix.crossings += rotation.crossings;
+        }
+        prefix
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dial::test_support::{EXAMPLE, expand, once, overflowing_landings};
+
+    #[test]
+    fn test_query_matches_replay() {
+        let index = PrefixIndex::new(Dial::new(100, 50), &once(&EXAMPLE)).unwrap();
+        assert_eq!(index.rotations(), EXAMPLE.len() as u64);
+        for start in 0..=EXAMPLE.len() {
+            for end in start..=EXAMPLE.len() {
+                let summary = index.query(start as u64..end as u64).unwrap();
+                let mut dial = Dial::new(100, 50);
+                for &turn in &EXAMPLE[..start] {
+                    dial.rotate(turn);
+                }
+                assert_eq!(summary.start_position, dial.position());
+                let (mut landings, mut crossings) = (0, 0);
+                for &turn in &EXAMPLE[start..end] {
+                    let rotation = dial.rotate(turn);
+                    landings += u64::from(rotation.position == 0);
+                    crossings += rotation.crossings;
+                }
+                assert_eq!(summary.end_position, dial.position());
+                assert_eq!((summary.landings, summary.crossings), (landings, crossings));
+            }
+        }
+    }
+
+    #[test]
+    fn test_query_bounds() {
+        let index = PrefixIndex::new(Dial::new(100, 50), &once(&EXAMPLE)).unwrap();
+        let whole = index.query(0..10).unwrap();
+        assert_eq!((whole.landings, whole.crossings), (3, 6));
+        assert_eq!(index.query(0..11), None);
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = index.query(5..4);
+        assert_eq!(reversed, None);
+    }
+
+    #[test]
+    fn test_query_inside_repeated_steps() {
+        let steps = [
+            Step::new(30, 7).unwrap(),
+            Step::once(-5),
+            Step::new(-25, 12).unwrap(),
+        ];
+        let expanded = expand(&steps);
+        let index = PrefixIndex::new(Dial::new(100, 50), &steps).unwrap();
+        let reference = PrefixIndex::new(Dial::new(100, 50), &expanded).unwrap();
+        assert_eq!(index.rotations(), 20);
+        for start in 0..=20 {
+            for end in start..=20 {
+                assert_eq!(index.query(start..end), reference.query(start..end));
+            }
+        }
+    }
+
+    #[test]
+    fn test_overflowing_counts_are_errors() {
+        let steps = overflowing_landings();
+        let index = PrefixIndex::new(Dial::new(100, 50), &steps[..3]).unwrap();
+        assert_eq!(index.rotations(), u64::MAX);
+        let whole = index.query(0..u64::MAX).unwrap();
+        assert_eq!(whole.landings, u64::MAX);
+        assert!(matches!(
+            PrefixIndex::new(Dial::new(100, 50), &steps),
+            Err(CountOverflow)
+        ));
+    }
+}
@@ -0,0 +1,374 @@
+// Generates rotation documents with known part 1 and part 2 passwords, for testing the solvers.
+
+use crate::dial::Dial;
+use crate::rng::Rng;
+use std::error::Error;
+use std::fmt;
+
+/// Most rotations `synthesize` generates; the document is hundreds of megabytes by then.
+pub const MAX_LENGTH: usize = 100_000_000;
+
+/// What the generated sequence must produce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    /// Rotations that leave the dial at 0.
+    pub part1: u64,
+    /// Clicks that reach 0.
+    pub part2: u64,
+    /// Number of rotations.
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SynthesisError {
+    /// More than [`MAX_LENGTH`] rotations.
+    TooLong {
+        length: usize,
+    },
+    TooManyLandings {
+        part1: u64,
+        length: usize,
+    },
+    /// On a one-number dial every rotation lands on 0.
+    SingleNumberDial {
+        part1: u64,
+        length: usize,
+    },
+    /// Landing on 0 from anywhere else takes at least one crossing.
+    TooFewCrossings {
+        part1: u64,
+        part2: u64,
+        start: i64,
+    },
+    TooManyCrossings {
+        part2: u64,
+        length: usize,
+    },
+}
+
+impl fmt::Display for SynthesisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SynthesisError::TooLong { length } => {
+                write!(
+                    f,
+                    "cannot generate {length} rotations, at most {MAX_LENGTH}"
+                )
+            }
+            SynthesisError::TooManyLandings { part1, length } => {
+                write!(f, "cannot land on 0 {part1} times in {length} rotations")
+            }
+            SynthesisError::SingleNumberDial { part1, length } => write!(
+                f,
+                "a dial with one number lands on 0 after every rotation, so part 1 must be {length}, not {part1}"
+            ),
+            SynthesisError::TooFewCrossings {
+                part1,
+                part2,
+                start,
+            } => write!(
+                f,
+                "landing on 0 {part1} times from {start} needs at least one crossing, got {part2}"
+            ),
+            SynthesisError::TooManyCrossings { part2, length } => write!(
+                f,
+                "{part2} crossings do not fit in {length} rotations without overflowing a distance"
+            ),
+        }
+    }
+}
+
+impl Error for SynthesisError {}
+
+/// Builds `target.length` turns that, starting from `dial`, give exactly the target passwords.
+///
+/// Landings are grouped into runs separated by other rotations. Only the first landing of a run
+/// has to cross 0 (unless the dial already starts there), so that bounds how many runs fit in
+/// the crossing budget. The remaining crossings become whole extra revolutions, mostly piled
+/// onto a few rotations so the output contains very long distances. Rotations between runs
+/// favour stopping just beside 0 to exercise turns that start and end next to it.
+pub fn synthesize(dial: Dial, target: Target, seed: u64) -> Result<Vec<i64>, SynthesisError> {
+    let Target {
+        part1,
+        part2,
+        length,
+    } = target;
+    let modulus = dial.modulus();
+    let start = dial.position();
+    let mut rng = Rng::new(seed);
+
+    if length > MAX_LENGTH {
+        return Err(SynthesisError::TooLong { length });
+    }
+    if part1 > length as u64 {
+        return Err(SynthesisError::TooManyLandings { part1, length });
+    }
+    // Keep every turn well inside i64 so callers can add positions without overflow
+    let max_revolutions = (i64::MAX / 4 / modulus) as u64;
+    if part2 > max_revolutions.saturating_mul(length as u64) {
+        return Err(SynthesisError::TooManyCrossings { part2, length });
+    }
+
+    if modulus == 1 {
+        if part1 != length as u64 {
+            return Err(SynthesisError::SingleNumberDial { part1, length });
+        }
+        let revolutions = spread(&mut rng, part2, length, max_revolutions);
+        return Ok(revolutions
+            .into_iter()
+            .map(|revolutions| random_sign(&mut rng) * revolutions as i64)
+            .collect());
+    }
+
+    let landings = landing_flags(&mut rng, start, target)?;
+    let mut position = start;
+    let mut base = Vec::with_capacity(length);
+    let mut base_crossings = 0;
+    for &lands in &landings {
+        let (turn, crossings) = if lands {
+            landing_turn(&mut rng, position, modulus)
+        } else {
+            let next = match rng.below(3) {
+                0 => 1,
+                1 => modulus - 1,
+                _ => 1 + rng.below(modulus as u64 - 1) as i64,
+            };
+            (passing_turn(&mut rng, position, next, modulus), 0)
+        };
+        base_crossings += crossings;
+        position = (position + turn).rem_euclid(modulus);
+        base.push(turn);
+    }
+
+    // Whole revolutions in the direction of travel add one crossing each and keep the endpoint
+    let extra = spread(&mut rng, part2 - base_crossings, length, max_revolutions);
+    Ok(base
+        .into_iter()
+        .zip(extra)
+        .map(|(turn, revolutions)| {
+            let sign = if turn == 0 {
+                random_sign(&mut rng)
+            } else {
+                turn.signum()
+            };
+            turn + sign * revolutions as i64 * modulus
+        })
+        .collect())
+}
+
+/// Formats turns as a rotation document, one `L<n>` or `R<n>` per line.
+pub fn format_rotations(turns: &[i64]) -> String {
+    let mut document = String::with_capacity(turns.len() * 5);
+    for &turn in turns {
+        let direction = if turn < 0 { 'L' } else { 'R' };
+        document.push(direction);
+        document.push_str(&turn.unsigned_abs().to_string());
+        document.push('\n');
+    }
+    document
+}
+
+// Decides which rotations land on 0, spending at most `part2` crossings on reaching it.
+fn landing_flags(rng: &mut Rng, start: i64, target: Target) -> Result<Vec<bool>, SynthesisError> {
+    let Target {
+        part1,
+        part2,
+        length,
+    } = target;
+    if part1 == 0 {
+        return Ok(vec![false; length]);
+    }
+
+    // Each run of landings is separated by at least one other rotation
+    let others = length as u64 - part1;
+    let max_runs = part1.min(others + 1);
+    // A run at the very start costs nothing when the dial already points at 0
+    let free_run = u64::from(start == 0);
+    let affordable_runs = max_runs.min(part2.saturating_add(free_run));
+    if affordable_runs == 0 {
+        return Err(SynthesisError::TooFewCrossings {
+            part1,
+            part2,
+            start,
+        });
+    }
+    let runs = 1 + rng.below(affordable_runs);
+    let must_lead = runs > part2;
+
+    let run_lengths = composition(rng, part1 - runs, runs as usize);
+    // Gaps: one before the first run, one between each pair of runs and one after the last
+    let mut gaps = composition(rng, others - (runs - 1), runs as usize + 1);
+    for gap in &mut gaps[1..runs as usize] {
+        *gap += 1;
+    }
+    if must_lead && gaps[0] > 0 {
+        let moved = gaps[0];
+        gaps[0] = 0;
+        gaps[runs as usize] += moved;
+    }
+
+    let mut flags = Vec::with_capacity(length);
+    for (index, &gap) in gaps.iter().enumerate() {
+        flags.extend(std::iter::repeat_n(false, gap as usize));
+        if let Some(&run) = run_lengths.get(index) {
+            flags.extend(std::iter::repeat_n(true, run as usize + 1));
+        }
+    }
+    Ok(flags)
+}
+
+// A turn from `position` that ends on 0, and the crossings it needs before extra revolutions.
+fn landing_turn(rng: &mut Rng, position: i64, modulus: i64) -> (i64, u64) {
+    if position == 0 {
+        (0, 0)
+    } else if rng.below(2) == 0 {
+        (modulus - position, 1)
+    } else {
+        (-position, 1)
+    }
+}
+
+// A turn from `position` to `next` (neither crossing nor ending on 0, as `next` is not 0).
+fn passing_turn(rng: &mut Rng, position: i64, next: i64, modulus: i64) -> i64 {
+    if position == 0 {
+        if rng.below(2) == 0 {
+            next
+        } else {
+            next - modulus
+        }
+    } else {
+        next - position
+    }
+}
+
+fn random_sign(rng: &mut Rng) -> i64 {
+    if rng.below(2) == 0 { -1 } else { 1 }
+}
+
+// Splits `total` into `parts` random non-negative values, each at most `cap`.
+fn spread(rng: &mut Rng, total: u64, parts: usize, cap: u64) -> Vec<u64> {
+    let mut values = vec![0u64; parts];
+    let mut remaining = total;
+    // A few random rotations take large chunks, so some distances span many revolutions
+    for _ in 0..64 {
+        if remaining == 0 {
+            break;
+        }
+        let index = rng.below(parts as u64) as usize;
+        let chunk = (1 + rng.below(remaining)).min(cap - values[index]);
+        values[index] += chunk;
+        remaining -= chunk;
+    }
+    for value in &mut values {
+        let chunk = remaining.min(cap - *value);
+        *value += chunk;
+        remaining -= chunk;
+    }
+    values
+}
+
+// Splits `total` into `parts` random non-negative values.
+fn composition(rng: &mut Rng, total: u64, parts: usize) -> Vec<u64> {
+    let mut cuts: Vec<u64> = (1..parts).map(|_| rng.below(total + 1)).collect();
+    cuts.sort_unstable();
+    let mut previous = 0;
+    cuts.into_iter()
+        .chain(std::iter::once(total))
+        .map(|cut| {
+            let part = cut - previous;
+            previous = cut;
+            part
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passwords(mut dial: Dial, turns: &[i64]) -> (u64, u64) {
+        let (mut part1, mut part2) = (0, 0);
+        for &turn in turns {
+            let rotation = dial.rotate(turn);
+            part1 += u64::from(rotation.position == 0);
+            part2 += rotation.crossings;
+        }
+        (part1, part2)
+    }
+
+    #[test]
+    fn test_hits_targets() {
+        for (modulus, start) in [(100, 50), (100, 0), (2, 1), (7, 3), (1, 0)] {
+            for length in [0, 1, 2, 5, 40] {
+                for part1 in 0..=length as u64 {
+                    for part2 in [0, 1, 3, 50, 1_000_000_000_000] {
+                        let dial = Dial::new(modulus, start);
+                        let target = Target {
+                            part1,
+                            part2,
+                            length,
+                        };
+                        for seed in 0..3 {
+                            match synthesize(dial, target, seed) {
+                                Ok(turns) => {
+                                    assert_eq!(turns.len(), length);
+                                    assert_eq!(passwords(dial, &turns), (part1, part2));
+                                }
+                                Err(SynthesisError::TooFewCrossings { .. }) => {
+                                    assert!(start != 0 && part1 > 0 && part2 == 0)
+                                }
+                                Err(SynthesisError::TooManyCrossings { .. }) => {
+                                    assert_eq!(length, 0)
+                                }
+                                Err(SynthesisError::SingleNumberDial { .. }) => {
+                                    assert!(modulus == 1 && part1 != length as u64)
+                                }
+                                Err(err) => panic!("unexpected {err}"),
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_impossible_targets() {
+        let dial = Dial::new(100, 50);
+        let target = Target {
+            part1: 6,
+            part2: 10,
+            length: 5,
+        };
+        assert_eq!(
+            synthesize(dial, target, 1),
+            Err(SynthesisError::TooManyLandings {
+                part1: 6,
+                length: 5
+            })
+        );
+        let target = Target {
+            part1: 0,
+            part2: u64::MAX,
+            length: 1,
+        };
+        assert!(matches!(
+            synthesize(dial, target, 1),
+            Err(SynthesisError::TooManyCrossings { .. })
+        ));
+        let target = Target {
+            part1: 1,
+            part2: 1,
+            length: usize::MAX,
+        };
+        assert_eq!(
+            synthesize(dial, target, 1),
+            Err(SynthesisError::TooLong { length: usize::MAX })
+        );
+    }
+
+    #[test]
+    fn test_format_rotations() {
+        assert_eq!(format_rotations(&[-68, 0, 48]), "L68\nR0\nR48\n");
+    }
+}
@@ -0,0 +1,219 @@
+// Per-rotation trace of a dial run, for diffing runs or loading into a spreadsheet.
+
+use crate::dial::Dial;
+use crate::parse::{Instruction, ParseError};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    Csv,
+    JsonLines,
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(TraceFormat::Csv),
+            "jsonl" | "json-lines" => Ok(TraceFormat::JsonLines),
+            _ => Err(format!("unknown trace format {s:?}, expected csv or jsonl")),
+        }
+    }
+}
+
+/// What happened during one rotation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceRecord {
+    /// 0-based position of the rotation in the sequence, counting each repetition separately.
+    pub index: u64,
+    pub instruction: String,
+    pub before: i64,
+    pub after: i64,
+    /// Whether the rotation left the dial at 0 (the part 1 rule).
+    pub landed_on_zero: bool,
+    /// Clicks that reached 0 during the rotation (the part 2 rule).
+    pub crossings: u64,
+}
+
+/// Replays instructions on a dial, yielding one record per rotation.
+///
+/// A repeated instruction such as `R10x500` yields one record for each of its repetitions.
+pub struct Trace<I> {
+    dial: Dial,
+    instructions: I,
+    // The instruction being repeated and how many repetitions are still to come
+    current: Option<(Instruction, u64)>,
+    index: u64,
+}
+
+pub fn trace<I>(dial: Dial, instructions: I) -> Trace<I::IntoIter>
+where
+    I: IntoIterator<Item = Result<Instruction, ParseError>>,
+{
+    Trace {
+        dial,
+        instructions: instructions.into_iter(),
+        current: None,
+        index: 0,
+    }
+}
+
+impl<I> Iterator for Trace<I>
+where
+    I: Iterator<Item = Result<Instruction, ParseError>>,
+{
+    type Item = Result<TraceRecord, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let instruction = loop {
+            match &mut self.current {
+                Some((instruction, remaining)) if *remaining > 0 => {
+                    *remaining -= 1;
+                    break instruction;
+                }
+                _ => match self.instructions.next()? {
+                    Ok(instruction) => {
+                        let repeat = instruction.step.repeat();
+                        self.current = Some((instruction, repeat));
+                    }
+                    Err(err) => return Some(Err(err)),
+                },
+            }
+        };
+
+        let before = self.dial.position();
+        let rotation = self.dial.rotate(instruction.step.turn());
+        let record = TraceRecord {
+            index: self.index,
+            instruction: instruction.text.clone(),
+            before,
+            after: rotation.position,
+            landed_on_zero: rotation.position == 0,
+            crossings: rotation.crossings,
+        };
+        self.index += 1;
+        Some(Ok(record))
+    }
+}
+
+/// Streams a trace to `out`, stopping at the first unparsable instruction.
+pub fn write_trace<W, I>(out: &mut W, format: TraceFormat, records: I) -> Result<(), Box<dyn Error>>
+where
+    W: Write,
+    I: IntoIterator<Item = Result<TraceRecord, ParseError>>,
+{
+    if format == TraceFormat::Csv {
+        writeln!(
+            out,
+            "index,instruction,before,after,landed_on_zero,crossings"
+        )?;
+    }
+    for record in records {
+        let record = record?;
+        let line = match format {
+            TraceFormat::Csv => format!(
+                "{},{},{},{},{},{}",
+                record.index,
+                csv_field(&record.instruction),
+                record.before,
+                record.after,
+                record.landed_on_zero,
+                record.crossings
+            ),
+            TraceFormat::JsonLines => format!(
+                "{{\"index\":{},\"instruction\":{},\"before\":{},\"after\":{},\"landed_on_zero\":{},\"crossings\":{}}}",
+                record.index,
+                json_string(&record.instruction),
+                record.before,
+                record.after,
+                record.landed_on_zero,
+                record.crossings
+            ),
+        };
+        writeln!(out, "{line}")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::instructions;
+
+    fn render(format: TraceFormat, input: &str) -> String {
+        let mut out = Vec::new();
+        let records = trace(Dial::new(100, 50), instructions(input.as_bytes()));
+        write_trace(&mut out, format, records).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_csv_trace() {
+        assert_eq!(
+            render(TraceFormat::Csv, "L68\nR48\nL150\n"),
+            "index,instruction,before,after,landed_on_zero,crossings\n\
+             0,L68,50,82,false,1\n\
+             1,R48,82,30,false,1\n\
+             2,L150,30,80,false,2\n"
+        );
+    }
+
+    #[test]
+    fn test_repeated_instructions_expand() {
+        assert_eq!(
+            render(TraceFormat::Csv, "R25x3, L1\n"),
+            "index,instruction,before,after,landed_on_zero,crossings\n\
+             0,R25x3,50,75,false,0\n\
+             1,R25x3,75,0,true,1\n\
+             2,R25x3,0,25,false,0\n\
+             3,L1,25,24,false,0\n"
+        );
+    }
+
+    #[test]
+    fn test_json_lines_trace() {
+        assert_eq!(
+            render(TraceFormat::JsonLines, "R50\n"),
+            "{\"index\":0,\"instruction\":\"R50\",\"before\":50,\"after\":0,\"landed_on_zero\":true,\"crossings\":1}\n"
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        assert_eq!(csv_field("R1,x\"y"), "\"R1,x\"\"y\"");
+        assert_eq!(json_string("a\"b\\c\u{1}"), "\"a\\\"b\\\\c\\u0001\"");
+    }
+}
@@ -1,68 +1,68 @@
 // Alternative solution for Day 2
 // Use this file to experiment with different approaches
+//
+// This one checks every ID in each range against the shared rule, which is slow on wide
+// ranges but makes a handy cross-check for the enumeration in main.rs.
 
-use std::fs;
+use day02::rule::narrow_sum;
+use day02::{Id, IdRange, InvalidRule, Options, SumOverflow, USAGE};
+use std::env;
 
-#[derive(Debug)]
-pub struct Range {
-    start: i64,
-    end: i64,
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::from_args(env::args().skip(1))?;
+    if options.help {
+        println!("{USAGE}");
+        return Ok(());
+    }
     println!("Testing alternative solution for Day 2");
 
-    let content = load_input("input.txt")?;
-
-    println!("Part 1: {}", solve_part1(&content));
-    println!("Part 2: {}", solve_part2(&content));
-    Ok(())
+    if options.report.is_some() || options.index.is_some() {
+        return Err("--report and --index are only supported by the main binary".into());
+    }
+    match options.width {
+        32 => run_with::<u32>(&options),
+        128 => run_with::<u128>(&options),
+        _ => run_with::<u64>(&options),
+    }
 }
 
-fn load_input(file_path: &str) -> Result<Vec<Range>, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(file_path)?;
-    let ranges = content
-        .trim()
-        .split(',')
-        .map(|line| -> Result<Range, Box<dyn std::error::Error>> {
-            let parts: Vec<&str> = line.split('-').collect();
-            if parts.len() != 2 {
-                return Err("Invalid range format".into());
-            }
-            Ok(Range {
-                start: parts[0].parse()?,
-                end: parts[1].parse()?,
-            })
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-
-    Ok(ranges)
-}
+fn run_with<T: Id>(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let radix = options.radix;
+    let content = options.load::<T>()?;
 
-fn part1_is_invalid(id: i64) -> bool {
-    let id_str = id.to_string();
-    let len = id_str.len();
-    len.is_multiple_of(2) && id_str[..len / 2] == id_str[len / 2..]
-}
-
-fn part2_is_invalid(id: i64) -> bool {
-    let id_str = id.to_string();
-    let len = id_str.len();
-    len.is_multiple_of(3)
-        && id_str[..len / 3] == id_str[len / 3..2 * len / 3]
-        && id_str[..len / 3] == id_str[2 * len / 3..]
-}
-
-pub fn solve_part1(nums: &[Range]) -> i64 {
-    nums.iter()
-        .flat_map(|range| range.start..=range.end)
-        .filter(|&id| part1_is_invalid(id))
-        .sum()
+    match options.rule {
+        Some(rule) => println!("{rule}: {}", solve(&content, rule, radix)?),
+        None => {
+            println!(
+                "Part 1 ({}): {}",
+                InvalidRule::ExactlyTwice,
+                solve(&content, InvalidRule::ExactlyTwice, radix)?
+            );
+            println!(
+                "Part 2 ({}): {}",
+                InvalidRule::AtLeastTwice,
+                solve(&content, InvalidRule::AtLeastTwice, radix)?
+            );
+        }
+    }
+    Ok(())
 }
 
-pub fn solve_part2(nums: &[Range]) -> i64 {
-    nums.iter()
-        .flat_map(|range| range.start..=range.end)
-        .filter(|&id| part2_is_invalid(id))
-        .sum()
+pub fn solve<T: Id>(nums: &[IdRange<T>], rule: InvalidRule, radix: u32) -> Result<T, SumOverflow> {
+    let total = nums
+        .iter()
+        .flat_map(|range| (range.start.into()..=range.end.into()).map(|id| (id, range.digits)))
+        .filter(|&(id, width): &(u128, _)| match width {
+            Some(width) => rule.is_invalid_padded(id, width, radix),
+            None => rule.is_invalid(id, radix),
+        })
+        .try_fold(0u128, |total, (id, _)| total.checked_add(id));
+    narrow_sum(total, rule)
 }
@@ -0,0 +1,503 @@
+// A sorted list of every invalid ID up to some bound, with prefix sums, for answering many
+// range queries with binary searches instead of arithmetic per range.
+//
+// Listing every repeated-digit ID below 10^18 would take about a billion entries, so a list
+// stops at MAX_INDEX_IDS. In base 10 that is every invalid ID below 10^15, under any rule;
+// `open_or_build` stops there, with a warning, and longer IDs are left to the arithmetic.
+
+use crate::{IdRange, InvalidRule};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Largest index `build` agrees to create. Each ID takes 24 bytes with its prefix sum, in
+/// memory and on disk, so this is about 1.2 GB; in base 10 it covers every ID below 10^15.
+pub const MAX_INDEX_IDS: u128 = 50_000_000;
+
+// Lists longer than this take long enough to build, and enough space, to warn about first
+const LARGE_INDEX_IDS: u128 = 1_000_000;
+
+// The last byte is the format version; version 2 stores the prefix sums
+const MAGIC: &[u8; 8] = b"DAY2IDX2";
+
+#[derive(Debug)]
+pub enum IndexError {
+    Io(io::Error),
+    /// The file is not an index, or is truncated.
+    Format(String),
+    /// Building would list more than [`MAX_INDEX_IDS`] IDs.
+    TooLarge {
+        rule: InvalidRule,
+        max: u64,
+        ids: u128,
+    },
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::Io(err) => write!(f, "{err}"),
+            IndexError::Format(problem) => write!(f, "not a valid index file: {problem}"),
+            IndexError::TooLarge { rule, max, ids } => write!(
+                f,
+                "an index of {rule} up to {max} would hold {ids} IDs, more than {MAX_INDEX_IDS}"
+            ),
+        }
+    }
+}
+
+impl Error for IndexError {}
+
+impl From<io::Error> for IndexError {
+    fn from(err: io::Error) -> Self {
+        IndexError::Io(err)
+    }
+}
+
+/// How many invalid IDs a range holds and their sum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeTotals {
+    pub count: u64,
+    pub sum: u128,
+}
+
+/// Every ID from 1 to `max` that is invalid under one rule in one base.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidIndex {
+    rule: InvalidRule,
+    radix: u32,
+    max: u64,
+    ids: Vec<u64>,
+    // Entry i is the sum of the first i IDs, so there is one more entry than `ids`
+    prefix: Vec<u128>,
+}
+
+impl InvalidIndex {
+    /// Lists the invalid IDs up to `max`, refusing up front if there would be too many.
+    pub fn build(rule: InvalidRule, radix: u32, max: u64) -> Result<Self, IndexError> {
+        let range = IdRange::new(1, max);
+        let ids = rule.count(&range, radix);
+        if ids > MAX_INDEX_IDS {
+            return Err(IndexError::TooLarge { rule, max, ids });
+        }
+        let ids = rule
+            .invalid_ids(&range, radix)
+            .map(|invalid| invalid.id)
+            .collect();
+        Ok(Self::from_ids(rule, radix, max, ids))
+    }
+
+    fn from_ids(rule: InvalidRule, radix: u32, max: u64, ids: Vec<u64>) -> Self {
+        let mut prefix = Vec::with_capacity(ids.len() + 1);
+        prefix.push(0);
+        // At most 5 * 10^7 IDs below 2^64 each, so the sums stay far below u128::MAX
+        for &id in &ids {
+            prefix.push(prefix.last().unwrap() + u128::from(id));
+        }
+        InvalidIndex {
+            rule,
+            radix,
+            max,
+            ids,
+            prefix,
+        }
+    }
+
+    pub fn rule(&self) -> InvalidRule {
+        self.rule
+    }
+
+    pub fn radix(&self) -> u32 {
+        self.radix
+    }
+
+    /// Largest ID the index covers.
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Count and sum of the invalid IDs in `range` using two binary searches, or `None` if the
+    /// range ends beyond `max`. A reversed range is empty.
+    pub fn query(&self, range: &IdRange<u64>) -> Option<RangeTotals> {
+        if range.end > self.max {
+            return None;
+        }
+        let first = self.ids.partition_point(|&id| id < range.start);
+        let last = self.ids.partition_point(|&id| id <= range.end).max(first);
+        Some(RangeTotals {
+            count: (last - first) as u64,
+            sum: self.prefix[last] - self.prefix[first],
+        })
+    }
+
+    // The prefix sums are stored after the IDs, so loading never has to recompute them.
+    fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        let rule = self.rule.to_string();
+        out.write_all(&(rule.len() as u32).to_le_bytes())?;
+        out.write_all(rule.as_bytes())?;
+        out.write_all(&self.radix.to_le_bytes())?;
+        out.write_all(&self.max.to_le_bytes())?;
+        out.write_all(&(self.ids.len() as u64).to_le_bytes())?;
+        for &id in &self.ids {
+            out.write_all(&id.to_le_bytes())?;
+        }
+        for &sum in &self.prefix {
+            out.write_all(&sum.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read_from(fields: &mut Fields<impl Read>) -> Result<Self, IndexError> {
+        let rule_len = fields.u32()? as usize;
+        // No rule name comes close, so a longer one is not worth allocating for
+        if rule_len > 64 {
+            return Err(IndexError::Format("unknown rule".to_string()));
+        }
+        let rule = String::from_utf8(fields.bytes(rule_len)?)
+            .ok()
+            .and_then(|rule| rule.parse().ok())
+            .ok_or_else(|| IndexError::Format("unknown rule".to_string()))?;
+        let radix = fields.u32()?;
+        if !(2..=36).contains(&radix) {
+            return Err(IndexError::Format(format!("base {radix} out of range")));
+        }
+        let max = fields.u64()?;
+        let len = fields.u64()?;
+        if u128::from(len) > MAX_INDEX_IDS {
+            return Err(IndexError::Format("too many IDs".to_string()));
+        }
+        let len = len as usize;
+
+        let mut ids = Vec::with_capacity(len);
+        for _ in 0..len {
+            ids.push(fields.u64()?);
+        }
+        if ids.windows(2).any(|pair| pair[0] >= pair[1]) || ids.last().is_some_and(|&id| id > max) {
+            return Err(IndexError::Format("IDs out of order".to_string()));
+        }
+        let mut prefix = Vec::with_capacity(len + 1);
+        for _ in 0..=len {
+            prefix.push(fields.u128()?);
+        }
+        // Checking the stored sums is one subtraction per ID, and keeps a corrupt file from
+        // giving wrong totals
+        let consistent = prefix[0] == 0
+            && prefix
+                .windows(2)
+                .zip(&ids)
+                .all(|(pair, &id)| pair[1].checked_sub(pair[0]) == Some(u128::from(id)));
+        if !consistent {
+            return Err(IndexError::Format(
+                "prefix sums do not match the IDs".to_string(),
+            ));
+        }
+        Ok(InvalidIndex {
+            rule,
+            radix,
+            max,
+            ids,
+            prefix,
+        })
+    }
+}
+
+/// The smallest bound of the form `radix^digits - 1` that covers `id`, so an index built for
+/// one input also serves others whose IDs have as many digits.
+pub fn covering_bound(id: u64, radix: u32) -> u64 {
+    let digits = id.checked_ilog(radix.into()).unwrap_or(0) + 1;
+    u64::from(radix)
+        .checked_pow(digits)
+        .map_or(u64::MAX, |next| next - 1)
+}
+
+// The widest bound up to `max` whose index of `rule` holds at most `MAX_INDEX_IDS` IDs: `max`
+// itself, or else the largest ID written with fewer digits that keeps the list small enough.
+fn affordable_bound(rule: InvalidRule, radix: u32, max: u64) -> u64 {
+    let mut bound = max;
+    while rule.count(&IdRange::new(1, bound), radix) > MAX_INDEX_IDS {
+        bound = covering_bound(bound / u64::from(radix), radix);
+    }
+    bound
+}
+
+/// Writes several indexes into one file.
+pub fn write_indexes(out: &mut impl Write, indexes: &[InvalidIndex]) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&(indexes.len() as u32).to_le_bytes())?;
+    for index in indexes {
+        index.write_to(out)?;
+    }
+    Ok(())
+}
+
+/// Reads the indexes written by [`write_indexes`], straight into their lists as the bytes
+/// arrive, so loading costs one pass over the file.
+pub fn read_indexes(input: &mut impl Read) -> Result<Vec<InvalidIndex>, IndexError> {
+    let mut fields = Fields(BufReader::new(input));
+    let magic: [u8; 8] = fields.array()?;
+    if magic[..7] == MAGIC[..7] && magic != *MAGIC {
+        return Err(IndexError::Format(
+            "written in an older format; delete it to rebuild".to_string(),
+        ));
+    }
+    if magic != *MAGIC {
+        return Err(IndexError::Format("missing header".to_string()));
+    }
+    let count = fields.u32()?;
+    let indexes = (0..count)
+        .map(|_| InvalidIndex::read_from(&mut fields))
+        .collect::<Result<Vec<_>, _>>()?;
+    if fields.0.read(&mut [0])? != 0 {
+        return Err(IndexError::Format("trailing data".to_string()));
+    }
+    Ok(indexes)
+}
+
+/// Loads the indexes in `path`, builds any of `rules` that are missing or do not reach `max`,
+/// and saves the file again if anything was built. Returns one index per rule, in order.
+///
+/// An index that would hold more than [`MAX_INDEX_IDS`] IDs stops at a smaller bound instead,
+/// so queries past it return `None` and are left to the arithmetic.
+pub fn open_or_build(
+    path: &Path,
+    rules: &[InvalidRule],
+    radix: u32,
+    max: u64,
+) -> Result<Vec<InvalidIndex>, IndexError> {
+    let mut stored = match File::open(path) {
+        Ok(mut file) => read_indexes(&mut file)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(err) => return Err(err.into()),
+    };
+    let bounds: Vec<u64> = rules
+        .iter()
+        .map(|&rule| affordable_bound(rule, radix, max))
+        .collect();
+    let covers = |index: &InvalidIndex, rule, max| {
+        index.rule == rule && index.radix == radix && index.max >= max
+    };
+
+    let mut built = false;
+    for (&rule, &bound) in rules.iter().zip(&bounds) {
+        if !stored.iter().any(|index| covers(index, rule, bound)) {
+            let ids = rule.count(&IdRange::new(1, bound), radix);
+            if bound < max {
+                eprintln!(
+                    "warning: an index of {rule} stops at {bound} to stay within {MAX_INDEX_IDS} IDs; larger IDs are summed by arithmetic"
+                );
+            }
+            if ids > LARGE_INDEX_IDS {
+                eprintln!(
+                    "warning: building an index of {rule} with {ids} IDs, about {} MB",
+                    ids * 24 / 1_000_000
+                );
+            }
+            stored.retain(|index| index.rule != rule || index.radix != radix);
+            stored.push(InvalidIndex::build(rule, radix, bound)?);
+            built = true;
+        }
+    }
+    if built {
+        // Write beside the file and rename, so an interrupted save never leaves half an index
+        let partial = path.with_extension("partial");
+        let mut out = BufWriter::new(File::create(&partial)?);
+        write_indexes(&mut out, &stored)?;
+        out.into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        fs::rename(&partial, path)?;
+    }
+
+    Ok(rules
+        .iter()
+        .zip(&bounds)
+        .map(|(&rule, &max)| {
+            let position = stored
+                .iter()
+                .position(|index| covers(index, rule, max))
+                .unwrap();
+            stored[position].clone()
+        })
+        .collect())
+}
+
+// Little-endian fields read one at a time from an index file.
+struct Fields<R>(BufReader<R>);
+
+impl<R: Read> Fields<R> {
+    fn bytes(&mut self, len: usize) -> Result<Vec<u8>, IndexError> {
+        let mut bytes = vec![0; len];
+        self.fill(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], IndexError> {
+        let mut bytes = [0; N];
+        self.fill(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn fill(&mut self, bytes: &mut [u8]) -> Result<(), IndexError> {
+        self.0.read_exact(bytes).map_err(|err| match err.kind() {
+            io::ErrorKind::UnexpectedEof => {
+                IndexError::Format("unexpected end of file".to_string())
+            }
+            _ => IndexError::Io(err),
+        })
+    }
+
+    fn u32(&mut self) -> Result<u32, IndexError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, IndexError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn u128(&mut self) -> Result<u128, IndexError> {
+        Ok(u128::from_le_bytes(self.array()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: [InvalidRule; 5] = [
+        InvalidRule::ExactlyTwice,
+        InvalidRule::AtLeastTwice,
+        InvalidRule::ExactlyK(3),
+        InvalidRule::AtLeastK(1),
+        InvalidRule::AtLeastK(3),
+    ];
+
+    #[test]
+    fn test_query_matches_rule() {
+        for radix in [2, 10] {
+            for rule in RULES {
+                let max = if radix == 2 { (1 << 16) - 1 } else { 999_999 };
+                let index = InvalidIndex::build(rule, radix, max).unwrap();
+                assert_eq!(
+                    index.len() as u128,
+                    rule.count(&IdRange::new(1, max), radix)
+                );
+                for start in (0..max).step_by(9_973) {
+                    for end in [start, start + 10, start + 5_000, max] {
+                        let range = IdRange::new(start, end.min(max));
+                        let totals = index.query(&range).unwrap();
+                        assert_eq!(u128::from(totals.count), rule.count(&range, radix));
+                        assert_eq!(Ok(totals.sum), rule.sum(&range, radix).map(u128::from));
+                    }
+                }
+                let beyond = IdRange::new(1, max + 1);
+                assert_eq!(index.query(&beyond), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let indexes = [
+            InvalidIndex::build(InvalidRule::ExactlyTwice, 10, 99_999_999).unwrap(),
+            InvalidIndex::build(InvalidRule::AtLeastK(3), 16, 0xffff_ffff).unwrap(),
+            InvalidIndex::build(InvalidRule::AtLeastTwice, 10, 0).unwrap(),
+        ];
+        let mut file = Vec::new();
+        write_indexes(&mut file, &indexes).unwrap();
+        assert_eq!(read_indexes(&mut file.as_slice()).unwrap(), indexes);
+
+        // The prefix sums are read back, not rebuilt, so a stored sum that disagrees is an error
+        let mut single = Vec::new();
+        write_indexes(&mut single, &indexes[..1]).unwrap();
+        // The last sum's low byte
+        let last = single.len() - 16;
+        single[last] ^= 1;
+        assert_eq!(
+            read_indexes(&mut single.as_slice())
+                .unwrap_err()
+                .to_string(),
+            "not a valid index file: prefix sums do not match the IDs"
+        );
+
+        let mut old = file.clone();
+        old[7] = b'1';
+        assert_eq!(
+            read_indexes(&mut old.as_slice()).unwrap_err().to_string(),
+            "not a valid index file: written in an older format; delete it to rebuild"
+        );
+        let truncated = &file[..file.len() - 3];
+        assert!(matches!(
+            read_indexes(&mut &truncated[..]),
+            Err(IndexError::Format(_))
+        ));
+        // This is what the command line prints
+        assert_eq!(
+            read_indexes(&mut &b"not an index"[..])
+                .unwrap_err()
+                .to_string(),
+            "not a valid index file: missing header"
+        );
+    }
+
+    #[test]
+    fn test_covering_bound() {
+        assert_eq!(covering_bound(0, 10), 9);
+        assert_eq!(covering_bound(1188511890, 10), 9_999_999_999);
+        assert_eq!(covering_bound(0x10, 16), 0xff);
+        assert_eq!(covering_bound(u64::MAX - 1, 2), u64::MAX);
+    }
+
+    #[test]
+    fn test_refuses_huge_index() {
+        let error = InvalidIndex::build(InvalidRule::AtLeastTwice, 10, 10u64.pow(18) - 1);
+        assert!(matches!(
+            error,
+            Err(IndexError::TooLarge { ids, .. }) if ids > 1_000_000_000
+        ));
+    }
+
+    #[test]
+    fn test_affordable_bound() {
+        assert_eq!(
+            affordable_bound(InvalidRule::AtLeastTwice, 10, 9_999),
+            9_999
+        );
+        // Lists stop at fifteen decimal digits rather than refusing longer IDs
+        for max in [10u64.pow(16) - 1, 10u64.pow(18) - 1, u64::MAX] {
+            assert_eq!(
+                affordable_bound(InvalidRule::AtLeastTwice, 10, max),
+                10u64.pow(15) - 1
+            );
+        }
+        assert_eq!(
+            affordable_bound(InvalidRule::ExactlyTwice, 2, u64::MAX),
+            2u64.pow(51) - 1
+        );
+    }
+
+    #[test]
+    fn test_open_or_build() {
+        let path = std::env::temp_dir().join(format!("day02-index-{}.bin", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let rules = [InvalidRule::ExactlyTwice, InvalidRule::AtLeastTwice];
+        let first = open_or_build(&path, &rules, 10, 9_999).unwrap();
+        let again = open_or_build(&path, &rules[1..], 10, 999).unwrap();
+        assert_eq!(again[0], first[1]);
+        // A wider bound replaces the stored index instead of adding a second one
+        let wider = open_or_build(&path, &rules[..1], 10, 999_999).unwrap();
+        assert_eq!(wider[0].max(), 999_999);
+        let stored = read_indexes(&mut File::open(&path).unwrap()).unwrap();
+        assert_eq!(stored.len(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+}
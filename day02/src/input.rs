@@ -0,0 +1,218 @@
+// Parsing of the range list: commas or newlines between entries, `#` comments, and single IDs.
+
+use crate::{Id, IdRange};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::num::ParseIntError;
+
+/// An entry that is neither an ID nor `first-last`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputError {
+    /// Byte offset of the entry from the start of the input, or of the sign for
+    /// [`Reason::Sign`].
+    pub offset: usize,
+    pub entry: String,
+    pub reason: Reason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// More than one dash, or a dash with nothing on one side.
+    Shape,
+    /// An ID that is not a number in the expected base, or too large for the ID type.
+    Id(ParseIntError),
+    /// FIRST and LAST of a fixed-width range written with different numbers of digits.
+    Widths,
+    /// An ID written with a `+`, which is not a digit even though Rust's parser allows it.
+    Sign,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid entry {:?} at byte {}: ",
+            self.entry, self.offset
+        )?;
+        match &self.reason {
+            Reason::Shape => write!(f, "expected an ID or FIRST-LAST"),
+            Reason::Id(err) => write!(f, "{err}"),
+            Reason::Widths => write!(f, "FIRST and LAST have different widths"),
+            Reason::Sign => write!(f, "IDs are written without a sign"),
+        }
+    }
+}
+
+impl Error for InputError {}
+
+/// Reads the ranges in the file `input`; see [`parse_ranges`] for the format.
+pub fn load_input<T: Id>(
+    input: &str,
+    radix: u32,
+    fixed_width: bool,
+) -> Result<Vec<IdRange<T>>, Box<dyn Error>> {
+    let content = fs::read_to_string(input)?;
+    Ok(parse_ranges(&content, radix, fixed_width)?)
+}
+
+/// Parses ranges written `first-last`, or a lone ID for a range of one, in base `radix`.
+///
+/// Entries are separated by commas or newlines and may have whitespace around them. Empty
+/// entries are skipped, and a `#` comments out the rest of its line.
+///
+/// With `fixed_width`, leading zeros are kept: each range is as wide as its IDs are written,
+/// so `000123000000-000123999999` holds IDs of 12 digits.
+pub fn parse_ranges<T: Id>(
+    text: &str,
+    radix: u32,
+    fixed_width: bool,
+) -> Result<Vec<IdRange<T>>, InputError> {
+    let mut ranges = Vec::new();
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.split('#').next().unwrap();
+        let mut entry_start = line_start;
+        for entry in content.split(',') {
+            let trimmed = entry.trim();
+            if !trimmed.is_empty() {
+                let offset = entry_start + (entry.len() - entry.trim_start().len());
+                ranges.push(
+                    parse_entry(trimmed, radix, fixed_width).map_err(|(at, reason)| {
+                        InputError {
+                            offset: offset + at,
+                            entry: trimmed.to_string(),
+                            reason,
+                        }
+                    })?,
+                );
+            }
+            entry_start += entry.len() + 1;
+        }
+        line_start += line.len();
+    }
+    Ok(ranges)
+}
+
+// The range in `entry`, or what is wrong with it and its byte offset within the entry.
+fn parse_entry<T: Id>(
+    entry: &str,
+    radix: u32,
+    fixed_width: bool,
+) -> Result<IdRange<T>, (usize, Reason)> {
+    let (first, last) = match entry.split_once('-') {
+        None => (entry, entry),
+        Some((first, last)) if !first.trim().is_empty() && !last.contains('-') => {
+            (first.trim(), last.trim())
+        }
+        Some(_) => return Err((0, Reason::Shape)),
+    };
+    let id = |text: &str| {
+        if text.starts_with('+') {
+            let at = text.as_ptr() as usize - entry.as_ptr() as usize;
+            return Err((at, Reason::Sign));
+        }
+        T::from_str_radix(text, radix).map_err(|err| (0, Reason::Id(err)))
+    };
+    let range = IdRange::new(id(first)?, id(last)?);
+    if !fixed_width {
+        Ok(range)
+    } else if first.len() == last.len() {
+        Ok(IdRange::padded(range.start, range.end, first.len() as u32))
+    } else {
+        Err((0, Reason::Widths))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Vec<(u64, u64)>, InputError> {
+        Ok(parse_ranges::<u64>(text, 10, false)?
+            .into_iter()
+            .map(|range| (range.start, range.end))
+            .collect())
+    }
+
+    #[test]
+    fn test_puzzle_format() {
+        assert_eq!(
+            parse("11-22,95-115,998-1012\n"),
+            Ok(vec![(11, 22), (95, 115), (998, 1012)])
+        );
+    }
+
+    #[test]
+    fn test_flexible_format() {
+        let text =
+            "# ranges to check\n  11-22 ,95 - 115\n\n998-1012,\n1188511885  # one ID\r\n,,7\n";
+        assert_eq!(
+            parse(text),
+            Ok(vec![
+                (11, 22),
+                (95, 115),
+                (998, 1012),
+                (1188511885, 1188511885),
+                (7, 7)
+            ])
+        );
+        assert_eq!(parse(""), Ok(vec![]));
+        assert_eq!(parse("# nothing\n"), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_errors_point_at_the_entry() {
+        let error = parse("11-22,\n  95-1x5,998-1012").unwrap_err();
+        assert_eq!(error.offset, 9);
+        assert_eq!(error.entry, "95-1x5");
+        assert_eq!(
+            error.to_string(),
+            "invalid entry \"95-1x5\" at byte 9: invalid digit found in string"
+        );
+
+        for (text, offset) in [("1-2-3", 0), ("5,-4", 2), ("5, 4-", 3), ("1,2,abc", 4)] {
+            assert_eq!(parse(text).unwrap_err().offset, offset, "{text}");
+        }
+        assert_eq!(parse("1-2-3").unwrap_err().reason, Reason::Shape);
+
+        // Rust's integer parser takes a leading +, but an ID is only digits
+        for (text, offset) in [("+11-22", 0), ("11-+22", 3), ("5, 7 - +9", 7)] {
+            let error = parse(text).unwrap_err();
+            assert_eq!(
+                (error.offset, error.reason),
+                (offset, Reason::Sign),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            parse("1,+11-+22").unwrap_err().to_string(),
+            "invalid entry \"+11-+22\" at byte 2: IDs are written without a sign"
+        );
+        let error = parse_ranges::<u64>("+0101", 10, true).unwrap_err();
+        assert_eq!(error.reason, Reason::Sign);
+    }
+
+    #[test]
+    fn test_base_and_width() {
+        let ranges = parse_ranges::<u32>("ff-1ab\n", 16, false).unwrap();
+        assert_eq!(ranges, [IdRange::new(0xff, 0x1ab)]);
+        let error = parse_ranges::<u32>("1-99999999999", 10, false).unwrap_err();
+        assert!(matches!(error.reason, Reason::Id(_)));
+    }
+
+    #[test]
+    fn test_fixed_width() {
+        let ranges = parse_ranges::<u64>("000123000000-000123999999, 0101\n0-9", 10, true).unwrap();
+        assert_eq!(
+            ranges,
+            [
+                IdRange::padded(123000000, 123999999, 12),
+                IdRange::padded(101, 101, 4),
+                IdRange::padded(0, 9, 1),
+            ]
+        );
+        let error = parse_ranges::<u64>("1-2,05-100", 10, true).unwrap_err();
+        assert_eq!((error.offset, error.reason), (4, Reason::Widths));
+    }
+}
@@ -0,0 +1,184 @@
+// Shared code for the Day 2 binaries: input parsing, command-line options and the
+// invalid-ID rules, so both binaries always agree on what a rule means.
+
+pub mod index;
+pub mod input;
+pub mod ranges;
+pub mod report;
+pub mod rule;
+
+pub use input::{InputError, load_input, parse_ranges};
+pub use ranges::Overlap;
+pub use report::ReportFormat;
+pub use rule::{InvalidRule, SumOverflow};
+
+use std::fmt;
+use std::num::ParseIntError;
+
+/// An unsigned integer type wide enough for the IDs being checked.
+///
+/// Detection and enumeration work on `u128` internally; the ID type decides which inputs
+/// parse and how large a sum may get before it is reported as an overflow.
+pub trait Id:
+    'static + Copy + Ord + fmt::Debug + fmt::Display + Into<u128> + TryFrom<u128>
+{
+    /// Type name for messages, such as `u64`.
+    const NAME: &'static str;
+
+    fn from_str_radix(text: &str, radix: u32) -> Result<Self, ParseIntError>;
+}
+
+macro_rules! impl_id {
+    ($($t:ty),*) => {
+        $(
+            impl Id for $t {
+                const NAME: &'static str = stringify!($t);
+
+                fn from_str_radix(text: &str, radix: u32) -> Result<Self, ParseIntError> {
+                    <$t>::from_str_radix(text, radix)
+                }
+            }
+        )*
+    };
+}
+
+impl_id!(u32, u64, u128);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdRange<T = u64> {
+    pub start: T,
+    pub end: T,
+    /// Digits every ID is zero-padded to, for IDs that are fixed-width strings. Leading zeros
+    /// then count as digits, so `000123000123` is `000123` twice.
+    pub digits: Option<u32>,
+}
+
+impl<T> IdRange<T> {
+    /// A range of IDs written without leading zeros, as in the puzzle.
+    pub fn new(start: T, end: T) -> Self {
+        IdRange {
+            start,
+            end,
+            digits: None,
+        }
+    }
+
+    /// A range of IDs zero-padded to `digits` digits.
+    pub fn padded(start: T, end: T, digits: u32) -> Self {
+        IdRange {
+            start,
+            end,
+            digits: Some(digits),
+        }
+    }
+}
+
+pub const USAGE: &str =
+    "usage: [--rule RULE] [--base N] [--width BITS] [--report FORMAT] [--overlap MODE] [--index FILE] [--fixed-width] [INPUT]
+
+RULE is exactly-twice, at-least-twice, exactly-<k> or at-least-<k>.
+Without --rule, part 1 uses exactly-twice and part 2 uses at-least-twice.
+N is the base (2 to 36) used for the IDs in INPUT and for finding repeated digits; default 10.
+BITS is the unsigned integer width (32, 64 or 128) for IDs and their sum; default 64.
+INPUT lists ranges as FIRST-LAST, or single IDs, separated by commas or newlines; # starts a
+comment.
+FORMAT is table or json, and lists every invalid ID of each range instead of only the totals.
+MODE is union (default), which counts an ID in overlapping ranges once, or multiplicity, which
+counts it once per range.
+FILE holds sorted lists of invalid IDs that answer each range with two binary searches. Lists
+missing from FILE, or too short for INPUT, are built and saved there first. A list stops at 50
+million IDs, about 1.2 GB: in base 10 it covers IDs below 10^15, not the 10^18 IDs can reach,
+and larger IDs are summed by arithmetic.
+--fixed-width keeps leading zeros: each range's IDs are strings as wide as they are written,
+so 000123000123 is 000123 twice.
+INPUT defaults to input.txt.";
+
+/// Command-line options understood by both binaries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub input: String,
+    /// A single rule to evaluate instead of the two puzzle parts.
+    pub rule: Option<InvalidRule>,
+    /// Base in which IDs are written and compared digit by digit.
+    pub radix: u32,
+    /// Bits in the integer type that holds IDs and sums: 32, 64 or 128.
+    pub width: u32,
+    /// Print a per-range breakdown in this format instead of the totals.
+    pub report: Option<ReportFormat>,
+    /// Whether IDs in overlapping ranges count once or once per range.
+    pub overlap: Overlap,
+    /// File of precomputed invalid IDs to answer the totals from.
+    pub index: Option<String>,
+    /// Treat IDs as zero-padded strings as wide as they are written in INPUT.
+    pub fixed_width: bool,
+    /// Print [`USAGE`] and do nothing else.
+    pub help: bool,
+}
+
+impl Options {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            input: "input.txt".to_string(),
+            rule: None,
+            radix: 10,
+            width: 64,
+            report: None,
+            overlap: Overlap::Union,
+            index: None,
+            fixed_width: false,
+            help: false,
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--rule" => {
+                    let rule = args.next().ok_or(USAGE)?;
+                    options.rule = Some(rule.parse()?);
+                }
+                "--base" => {
+                    let base = args.next().ok_or(USAGE)?;
+                    options.radix = match base.parse() {
+                        Ok(radix @ 2..=36) => radix,
+                        _ => return Err(format!("invalid base {base:?}, expected 2 to 36")),
+                    };
+                }
+                "--width" => {
+                    let width = args.next().ok_or(USAGE)?;
+                    options.width = match width.parse() {
+                        Ok(width @ (32 | 64 | 128)) => width,
+                        _ => {
+                            return Err(format!("invalid width {width:?}, expected 32, 64 or 128"));
+                        }
+                    };
+                }
+                "--report" => {
+                    let format = args.next().ok_or(USAGE)?;
+                    options.report = Some(format.parse()?);
+                }
+                "--overlap" => {
+                    let mode = args.next().ok_or(USAGE)?;
+                    options.overlap = mode.parse()?;
+                }
+                "--index" => options.index = Some(args.next().ok_or(USAGE)?),
+                "--fixed-width" => options.fixed_width = true,
+                "-h" | "--help" => options.help = true,
+                _ if arg.starts_with("--") => {
+                    return Err(format!("unknown option {arg}\n\n{USAGE}"));
+                }
+                _ => options.input = arg,
+            }
+        }
+        Ok(options)
+    }
+
+    /// Reads the input ranges and normalizes them under `overlap`, warning on stderr about
+    /// any range written more than once.
+    pub fn load<T: Id>(&self) -> Result<Vec<IdRange<T>>, Box<dyn std::error::Error>> {
+        let ranges = load_input::<T>(&self.input, self.radix, self.fixed_width)?;
+        let normalized = ranges::normalize(&ranges, self.overlap, self.radix)?;
+        for duplicate in &normalized.duplicates {
+            eprintln!("warning: {duplicate}");
+        }
+        Ok(normalized.ranges)
+    }
+}
@@ -33,61 +33,127 @@
 //
 // What do you get if you add up all of the invalid IDs?
 
-use std::fs;
+use day02::index::{self, InvalidIndex};
+use day02::report::{Report, write_reports};
+use day02::rule::narrow_sum;
+use day02::{Id, IdRange, InvalidRule, Options, SumOverflow, USAGE};
+use std::env;
+use std::io;
+use std::path::Path;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("Part 1: {}", solve_part1("input.txt")?);
-    println!("Part 2: {}", solve_part2("input.txt")?);
-    Ok(())
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct IdRange {
-    start: i64,
-    end: i64,
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::from_args(env::args().skip(1))?;
+    if options.help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    match options.width {
+        32 => run_with::<u32>(&options),
+        128 => run_with::<u128>(&options),
+        _ => run_with::<u64>(&options),
+    }
 }
 
-fn load_input(input: &str) -> Result<Vec<IdRange>, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(input)?;
-    let ranges = content
-        .trim()
-        .split(',')
-        .map(|line| -> Result<IdRange, Box<dyn std::error::Error>> {
-            let parts: Vec<&str> = line.split('-').collect();
-            if parts.len() != 2 {
-                return Err("Invalid range format".into());
-            }
-            Ok(IdRange {
-                start: parts[0].parse()?,
-                end: parts[1].parse()?,
-            })
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-    
-    Ok(ranges)
+fn run_with<T: Id>(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let radix = options.radix;
+    let ranges = options.load::<T>()?;
+    let rules = match options.rule {
+        Some(rule) => vec![rule],
+        None => vec![InvalidRule::ExactlyTwice, InvalidRule::AtLeastTwice],
+    };
+
+    if let Some(format) = options.report {
+        let reports = rules
+            .into_iter()
+            .map(|rule| Report::new(&ranges, rule, radix))
+            .collect::<Result<Vec<_>, _>>()?;
+        write_reports(&mut io::stdout().lock(), format, &reports)?;
+        return Ok(());
+    }
+
+    let indexes = match &options.index {
+        Some(path) => {
+            // Ranges past u64 or past the index's cap, and fixed-width ones, are left to the
+            // arithmetic in `solve`
+            let largest = ranges
+                .iter()
+                .filter(|range| range.digits.is_none())
+                .filter_map(|range| u64::try_from(range.end.into()).ok())
+                .max()
+                .unwrap_or(0);
+            let max = index::covering_bound(largest, radix);
+            index::open_or_build(Path::new(path), &rules, radix, max)?
+        }
+        None => Vec::new(),
+    };
+
+    // Every answer is labelled with the rule that produced it
+    match options.rule {
+        Some(rule) => println!("{rule}: {}", solve(&ranges, rule, radix, &indexes)?),
+        None => {
+            println!(
+                "Part 1 ({}): {}",
+                InvalidRule::ExactlyTwice,
+                solve_part1(&ranges, radix, &indexes)?
+            );
+            println!(
+                "Part 2 ({}): {}",
+                InvalidRule::AtLeastTwice,
+                solve_part2(&ranges, radix, &indexes)?
+            );
+        }
+    }
+    Ok(())
 }
 
-fn solve_part1(input: &str) -> Result<i64, Box<dyn std::error::Error>> {
-    let ranges = load_input(input)?;
-    let invalid_sum = ranges
+// Answers from a matching index where one covers the range, and by arithmetic otherwise.
+fn solve<T: Id>(
+    ranges: &[IdRange<T>],
+    rule: InvalidRule,
+    radix: u32,
+    indexes: &[InvalidIndex],
+) -> Result<T, SumOverflow> {
+    let Some(index) = indexes
         .iter()
-        .flat_map(|range| range.start..=range.end)
-        .filter(|&id| is_part1_invalid(id))
-        .sum();
-
-    Ok(invalid_sum)
+        .find(|index| index.rule() == rule && index.radix() == radix)
+    else {
+        return rule.total(ranges, radix);
+    };
+    let narrow = |id: T| u64::try_from(id.into()).ok();
+    let total = ranges.iter().try_fold(0u128, |total, range| {
+        let indexed = match (narrow(range.start), narrow(range.end), range.digits) {
+            (Some(start), Some(end), None) => index.query(&IdRange::new(start, end)),
+            _ => None,
+        };
+        let sum = match indexed {
+            Some(totals) => totals.sum,
+            None => {
+                let wide = IdRange {
+                    start: range.start.into(),
+                    end: range.end.into(),
+                    digits: range.digits,
+                };
+                rule.sum(&wide, radix).ok()?
+            }
+        };
+        total.checked_add(sum)
+    });
+    narrow_sum(total, rule)
 }
 
-fn is_part1_invalid(id: i64) -> bool {
-    let id_str = id.to_string();
-    let len = id_str.len();
-    
-    if len.is_multiple_of(2) {
-        let (first_half, second_half) = id_str.split_at(len / 2);
-        first_half == second_half
-    } else {
-        false
-    }
+fn solve_part1<T: Id>(
+    ranges: &[IdRange<T>],
+    radix: u32,
+    indexes: &[InvalidIndex],
+) -> Result<T, SumOverflow> {
+    solve(ranges, InvalidRule::ExactlyTwice, radix, indexes)
 }
 
 // --- Part Two ---
@@ -110,30 +176,10 @@ fn is_part1_invalid(id: i64) -> bool {
 // 2121212118-2121212124 now has one invalid ID, 2121212121.
 // Adding up all the invalid IDs in this example produces 4174379265.
 
-fn solve_part2(input: &str) -> Result<i64, Box<dyn std::error::Error>> {
-    let ranges = load_input(input)?;
-    let invalid_sum = ranges
-        .iter()
-        .flat_map(|range| range.start..=range.end)
-        .filter(|&id| is_part2_invalid(id))
-        .sum();
-
-    Ok(invalid_sum)
-}
-
-fn is_part2_invalid(id: i64) -> bool {
-    let id_str = id.to_string();
-    let len = id_str.len();
-
-    for sub_len in 1..=(len / 2) {
-        if len.is_multiple_of(sub_len) {
-            let sub_str = &id_str[0..sub_len];
-            let repeated_str = sub_str.repeat(len / sub_len);
-            if repeated_str == id_str {
-                return true;
-            }
-        }
-    }
-
-    false
+fn solve_part2<T: Id>(
+    ranges: &[IdRange<T>],
+    radix: u32,
+    indexes: &[InvalidIndex],
+) -> Result<T, SumOverflow> {
+    solve(ranges, InvalidRule::AtLeastTwice, radix, indexes)
 }
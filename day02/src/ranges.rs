@@ -0,0 +1,250 @@
+// Validation and merging of the input ranges before any rule is applied.
+
+use crate::rule::{format_id, format_range};
+use crate::{Id, IdRange};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// How IDs covered by more than one range are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    /// Merge overlapping and adjacent ranges, so every ID counts once.
+    Union,
+    /// Keep the ranges as written, so an ID counts once for every range holding it.
+    Multiplicity,
+}
+
+impl FromStr for Overlap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "union" => Ok(Overlap::Union),
+            "multiplicity" => Ok(Overlap::Multiplicity),
+            _ => Err(format!(
+                "unknown overlap mode {s:?}, expected union or multiplicity"
+            )),
+        }
+    }
+}
+
+/// A range whose first ID is after its last. Positions count from 1 in input order, and
+/// `radix` is the base the input was written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReversedRange<T> {
+    pub position: usize,
+    pub range: IdRange<T>,
+    pub radix: u32,
+}
+
+impl<T: Id> fmt::Display for ReversedRange<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.range.digits.unwrap_or(0);
+        write!(
+            f,
+            "range {} starts at {} but ends at {}",
+            self.position,
+            format_id(self.range.start.into(), self.radix, digits),
+            format_id(self.range.end.into(), self.radix, digits)
+        )
+    }
+}
+
+impl<T: Id> Error for ReversedRange<T> {}
+
+/// A range written more than once, which is worth a warning but not an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Duplicate<T> {
+    pub position: usize,
+    pub first: usize,
+    pub range: IdRange<T>,
+    pub radix: u32,
+}
+
+impl<T: Id> fmt::Display for Duplicate<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "range {} ({}) repeats range {}",
+            self.position,
+            format_range(&self.range, self.radix),
+            self.first
+        )
+    }
+}
+
+/// The ranges to sum under `overlap`, with every repeated range listed as a duplicate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Normalized<T> {
+    pub ranges: Vec<IdRange<T>>,
+    pub duplicates: Vec<Duplicate<T>>,
+}
+
+/// Checks that every range runs forwards and, for [`Overlap::Union`], merges the ranges that
+/// overlap or touch into sorted, disjoint ones. Ranges of different widths are never merged,
+/// as `0101` padded to four digits and 101 are different IDs. [`Overlap::Multiplicity`] keeps
+/// the input order. Errors and duplicates write IDs in base `radix`, as the input does.
+pub fn normalize<T: Id>(
+    ranges: &[IdRange<T>],
+    overlap: Overlap,
+    radix: u32,
+) -> Result<Normalized<T>, ReversedRange<T>> {
+    for (index, &range) in ranges.iter().enumerate() {
+        if range.start > range.end {
+            return Err(ReversedRange {
+                position: index + 1,
+                range,
+                radix,
+            });
+        }
+    }
+
+    // Stable, so equal ranges stay in input order and the first of them comes first
+    let mut order: Vec<usize> = (0..ranges.len()).collect();
+    order.sort_by_key(|&index| (ranges[index].digits, ranges[index].start, ranges[index].end));
+    let mut duplicates = Vec::new();
+    let mut first = order.first().copied().unwrap_or(0);
+    for pair in order.windows(2) {
+        if ranges[pair[0]] == ranges[pair[1]] {
+            duplicates.push(Duplicate {
+                position: pair[1] + 1,
+                first: first + 1,
+                range: ranges[pair[1]],
+                radix,
+            });
+        } else {
+            first = pair[1];
+        }
+    }
+    duplicates.sort_by_key(|duplicate| duplicate.position);
+
+    let ranges = match overlap {
+        Overlap::Multiplicity => ranges.to_vec(),
+        Overlap::Union => {
+            let mut merged: Vec<IdRange<T>> = Vec::with_capacity(ranges.len());
+            for range in order.into_iter().map(|index| ranges[index]) {
+                match merged.last_mut() {
+                    Some(last) if touches(*last, range) => {
+                        last.end = last.end.max(range.end);
+                    }
+                    _ => merged.push(range),
+                }
+            }
+            merged
+        }
+    };
+    Ok(Normalized { ranges, duplicates })
+}
+
+// Whether `next`, which starts no earlier than `last`, overlaps it or starts right after it.
+fn touches<T: Id>(last: IdRange<T>, next: IdRange<T>) -> bool {
+    let end: u128 = last.end.into();
+    last.digits == next.digits
+        && end
+            .checked_add(1)
+            .is_none_or(|after| next.start.into() <= after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(bounds: &[(u64, u64)]) -> Vec<IdRange> {
+        bounds
+            .iter()
+            .map(|&(start, end)| IdRange::new(start, end))
+            .collect()
+    }
+
+    #[test]
+    fn test_union_merges_overlapping_and_adjacent() {
+        let input = ranges(&[(50, 82), (1, 5), (83, 113), (3, 9), (200, 300), (11, 12)]);
+        let normalized = normalize(&input, Overlap::Union, 10).unwrap();
+        assert_eq!(
+            normalized.ranges,
+            ranges(&[(1, 9), (11, 12), (50, 113), (200, 300)])
+        );
+        assert!(normalized.duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_multiplicity_keeps_input() {
+        let input = ranges(&[(50, 82), (60, 70), (50, 82)]);
+        let normalized = normalize(&input, Overlap::Multiplicity, 10).unwrap();
+        assert_eq!(normalized.ranges, input);
+        assert_eq!(normalized.duplicates.len(), 1);
+    }
+
+    #[test]
+    fn test_duplicates_point_at_first_occurrence() {
+        let input = ranges(&[(5, 9), (1, 2), (5, 9), (1, 2), (5, 9)]);
+        let normalized = normalize(&input, Overlap::Union, 10).unwrap();
+        assert_eq!(normalized.ranges, ranges(&[(1, 2), (5, 9)]));
+        let pairs: Vec<(usize, usize)> = normalized
+            .duplicates
+            .iter()
+            .map(|duplicate| (duplicate.position, duplicate.first))
+            .collect();
+        assert_eq!(pairs, [(3, 1), (4, 2), (5, 1)]);
+        assert_eq!(
+            normalized.duplicates[0].to_string(),
+            "range 3 (5-9) repeats range 1"
+        );
+    }
+
+    #[test]
+    fn test_widths_stay_apart() {
+        let input = [
+            IdRange::padded(0u64, 99, 4),
+            IdRange::new(50, 150),
+            IdRange::padded(100, 200, 4),
+            IdRange::padded(100, 200, 3),
+        ];
+        let normalized = normalize(&input, Overlap::Union, 10).unwrap();
+        assert_eq!(
+            normalized.ranges,
+            [
+                IdRange::new(50, 150),
+                IdRange::padded(100, 200, 3),
+                IdRange::padded(0, 200, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reversed_range_is_an_error() {
+        let input = ranges(&[(1, 5), (9, 7)]);
+        let error = normalize(&input, Overlap::Multiplicity, 10).unwrap_err();
+        assert_eq!(error.position, 2);
+        assert_eq!(error.to_string(), "range 2 starts at 9 but ends at 7");
+    }
+
+    #[test]
+    fn test_messages_use_the_input_base() {
+        let input = ranges(&[(0x11, 0x20), (0x11, 0x20), (0xff, 0xa)]);
+        let error = normalize(&input, Overlap::Union, 16).unwrap_err();
+        assert_eq!(error.to_string(), "range 3 starts at ff but ends at a");
+        let normalized = normalize(&input[..2], Overlap::Union, 16).unwrap();
+        assert_eq!(
+            normalized.duplicates[0].to_string(),
+            "range 2 (11-20) repeats range 1"
+        );
+        let padded = [IdRange::padded(5u64, 9, 3), IdRange::padded(5, 9, 3)];
+        let normalized = normalize(&padded, Overlap::Union, 10).unwrap();
+        assert_eq!(
+            normalized.duplicates[0].to_string(),
+            "range 2 (005-009) repeats range 1"
+        );
+    }
+
+    #[test]
+    fn test_union_at_the_top_of_the_type() {
+        let input = [
+            IdRange::new(u32::MAX - 5, u32::MAX),
+            IdRange::new(u32::MAX - 9, u32::MAX - 6),
+        ];
+        let normalized = normalize(&input, Overlap::Union, 10).unwrap();
+        assert_eq!(normalized.ranges, [IdRange::new(u32::MAX - 9, u32::MAX)]);
+    }
+}
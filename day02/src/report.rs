@@ -0,0 +1,270 @@
+// Per-range breakdown of the invalid IDs, like the worked example in the puzzle text.
+
+use crate::rule::{InvalidIds, format_id, format_range};
+use crate::{Id, IdRange, InvalidRule, SumOverflow};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Aligned columns, one range per row.
+    Table,
+    /// A JSON array with one object per rule.
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(ReportFormat::Table),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!(
+                "unknown report format {s:?}, expected table or json"
+            )),
+        }
+    }
+}
+
+/// The invalid IDs of one range under one rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeReport<T> {
+    pub rule: InvalidRule,
+    pub radix: u32,
+    pub range: IdRange<T>,
+    pub count: u128,
+    pub sum: T,
+}
+
+impl<T: Id> RangeReport<T> {
+    pub fn new(range: IdRange<T>, rule: InvalidRule, radix: u32) -> Result<Self, SumOverflow> {
+        Ok(RangeReport {
+            rule,
+            radix,
+            range,
+            count: rule.count(&range, radix),
+            sum: rule.sum(&range, radix)?,
+        })
+    }
+
+    /// The IDs behind `count` and `sum`, generated only as they are read.
+    pub fn invalid_ids(&self) -> InvalidIds<T> {
+        self.rule.invalid_ids(&self.range, self.radix)
+    }
+}
+
+/// Every range of the input under one rule, with the same total the solvers print.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report<T> {
+    pub rule: InvalidRule,
+    pub radix: u32,
+    pub total: T,
+    pub ranges: Vec<RangeReport<T>>,
+}
+
+impl<T: Id> Report<T> {
+    pub fn new(ranges: &[IdRange<T>], rule: InvalidRule, radix: u32) -> Result<Self, SumOverflow> {
+        Ok(Report {
+            rule,
+            radix,
+            total: rule.total(ranges, radix)?,
+            ranges: ranges
+                .iter()
+                .map(|&range| RangeReport::new(range, rule, radix))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Writes `reports` in `format`. IDs are written in the base of the input and sums in decimal,
+/// matching the solvers' output. The invalid IDs are streamed straight from their iterators.
+pub fn write_reports<T: Id>(
+    out: &mut impl Write,
+    format: ReportFormat,
+    reports: &[Report<T>],
+) -> io::Result<()> {
+    match format {
+        ReportFormat::Table => reports.iter().enumerate().try_for_each(|(index, report)| {
+            if index > 0 {
+                writeln!(out)?;
+            }
+            write_table(out, report)
+        }),
+        ReportFormat::Json => {
+            writeln!(out, "[")?;
+            for (index, report) in reports.iter().enumerate() {
+                write_json(out, report)?;
+                writeln!(out, "{}", if index + 1 < reports.len() { "," } else { "" })?;
+            }
+            writeln!(out, "]")
+        }
+    }
+}
+
+fn write_table<T: Id>(out: &mut impl Write, report: &Report<T>) -> io::Result<()> {
+    let radix = report.radix;
+    let ranges: Vec<String> = report
+        .ranges
+        .iter()
+        .map(|range| format_range(&range.range, radix))
+        .collect();
+    let range_width = column_width("range", ranges.iter().map(String::len));
+    let count_width = column_width(
+        "count",
+        report.ranges.iter().map(|r| r.count.to_string().len()),
+    );
+    let sum_width = column_width("sum", report.ranges.iter().map(|r| r.sum.to_string().len()));
+
+    writeln!(out, "{}: {}", report.rule, report.total)?;
+    writeln!(
+        out,
+        "{:<range_width$}  {:>count_width$}  {:>sum_width$}  invalid IDs (pattern x repeats)",
+        "range", "count", "sum"
+    )?;
+    for (range, text) in report.ranges.iter().zip(&ranges) {
+        let width = range.range.digits.unwrap_or(0);
+        write!(
+            out,
+            "{text:<range_width$}  {:>count_width$}  {:>sum_width$}",
+            range.count, range.sum
+        )?;
+        for (position, invalid) in range.invalid_ids().enumerate() {
+            write!(
+                out,
+                "{}{} ({} x {})",
+                if position > 0 { " " } else { "  " },
+                format_id(invalid.id.into(), radix, width),
+                format_id(invalid.pattern.into(), radix, width / invalid.repeats),
+                invalid.repeats
+            )?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+// Every value is either a number or digits in base 2 to 36, so nothing needs escaping.
+fn write_json<T: Id>(out: &mut impl Write, report: &Report<T>) -> io::Result<()> {
+    let radix = report.radix;
+    writeln!(
+        out,
+        "  {{\"rule\": \"{}\", \"base\": {radix}, \"total\": {}, \"ranges\": [",
+        report.rule, report.total
+    )?;
+    for (index, range) in report.ranges.iter().enumerate() {
+        let width = range.range.digits.unwrap_or(0);
+        write!(
+            out,
+            "    {{\"start\": \"{}\", \"end\": \"{}\", \"count\": {}, \"sum\": {}, \"invalid\": [",
+            format_id(range.range.start.into(), radix, width),
+            format_id(range.range.end.into(), radix, width),
+            range.count,
+            range.sum
+        )?;
+        for (position, invalid) in range.invalid_ids().enumerate() {
+            write!(
+                out,
+                "{}{{\"id\": \"{}\", \"pattern\": \"{}\", \"repeats\": {}}}",
+                if position > 0 { ", " } else { "" },
+                format_id(invalid.id.into(), radix, width),
+                format_id(invalid.pattern.into(), radix, width / invalid.repeats),
+                invalid.repeats
+            )?;
+        }
+        let separator = if index + 1 < report.ranges.len() {
+            ","
+        } else {
+            ""
+        };
+        writeln!(out, "]}}{separator}")?;
+    }
+    write!(out, "  ]}}")
+}
+
+fn column_width(header: &str, cells: impl Iterator<Item = usize>) -> usize {
+    cells.max().unwrap_or(0).max(header.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Vec<IdRange> {
+        [(11, 22), (95, 115), (998, 1012), (1698522, 1698528)]
+            .into_iter()
+            .map(|(start, end)| IdRange::new(start, end))
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_puzzle_breakdown() {
+        let report = Report::new(&example(), InvalidRule::AtLeastTwice, 10).unwrap();
+        let ids: Vec<Vec<(u64, u64, u32)>> = report
+            .ranges
+            .iter()
+            .map(|range| {
+                range
+                    .invalid_ids()
+                    .map(|invalid| (invalid.id, invalid.pattern, invalid.repeats))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                vec![(11, 1, 2), (22, 2, 2)],
+                vec![(99, 9, 2), (111, 1, 3)],
+                vec![(999, 9, 3), (1010, 10, 2)],
+                vec![],
+            ]
+        );
+        let counts: Vec<u128> = report.ranges.iter().map(|range| range.count).collect();
+        assert_eq!(counts, [2, 2, 2, 0]);
+        assert_eq!(report.total, 11 + 22 + 99 + 111 + 999 + 1010);
+    }
+
+    #[test]
+    fn test_table() {
+        let report = Report::new(&example()[..2], InvalidRule::ExactlyTwice, 10).unwrap();
+        let mut out = Vec::new();
+        write_reports(&mut out, ReportFormat::Table, &[report]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "exactly-twice: 132
+range   count  sum  invalid IDs (pattern x repeats)
+11-22       2   33  11 (1 x 2) 22 (2 x 2)
+95-115      1   99  99 (9 x 2)
+"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let ranges = [IdRange::new(0x10u64, 0x20)];
+        let reports = [
+            Report::new(&ranges, InvalidRule::ExactlyTwice, 16).unwrap(),
+            Report::new(&ranges[..0], InvalidRule::AtLeastTwice, 16).unwrap(),
+        ];
+        let mut out = Vec::new();
+        write_reports(&mut out, ReportFormat::Json, &reports).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            r#"[
+  {"rule": "exactly-twice", "base": 16, "total": 17, "ranges": [
+    {"start": "10", "end": "20", "count": 1, "sum": 17, "invalid": [{"id": "11", "pattern": "1", "repeats": 2}]}
+  ]},
+  {"rule": "at-least-twice", "base": 16, "total": 0, "ranges": [
+  ]}
+]
+"#
+        );
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("table".parse(), Ok(ReportFormat::Table));
+        assert_eq!("json".parse(), Ok(ReportFormat::Json));
+        assert!("csv".parse::<ReportFormat>().is_err());
+    }
+}
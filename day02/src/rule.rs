@@ -0,0 +1,796 @@
+// Rules deciding which product IDs are invalid, shared by both Day 2 binaries.
+
+use crate::{Id, IdRange};
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// How many times an ID's digits must repeat for the ID to be invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidRule {
+    /// Some pattern repeated exactly twice, such as 6464 (the part 1 rule).
+    ExactlyTwice,
+    /// Some pattern repeated two or more times, such as 121212 (the part 2 rule).
+    AtLeastTwice,
+    /// Some pattern repeated exactly k times; 111111 counts for k = 2, 3 and 6.
+    ExactlyK(u32),
+    /// Some pattern repeated k or more times.
+    AtLeastK(u32),
+}
+
+/// The sum of invalid IDs under `rule` does not fit in the ID type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SumOverflow {
+    pub rule: InvalidRule,
+    pub width: &'static str,
+}
+
+impl SumOverflow {
+    pub fn new<T: Id>(rule: InvalidRule) -> Self {
+        SumOverflow {
+            rule,
+            width: T::NAME,
+        }
+    }
+}
+
+impl fmt::Display for SumOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the sum of invalid IDs under {} does not fit in a {}",
+            self.rule, self.width
+        )
+    }
+}
+
+impl Error for SumOverflow {}
+
+/// Narrows a `u128` sum, where `None` means it had already overflowed.
+pub fn narrow_sum<T: Id>(sum: Option<u128>, rule: InvalidRule) -> Result<T, SumOverflow> {
+    sum.and_then(|sum| T::try_from(sum).ok())
+        .ok_or(SumOverflow::new::<T>(rule))
+}
+
+impl InvalidRule {
+    // The same rule as (repetitions, whether more repetitions also count)
+    fn repetitions(self) -> (u32, bool) {
+        match self {
+            InvalidRule::ExactlyTwice => (2, false),
+            InvalidRule::AtLeastTwice => (2, true),
+            InvalidRule::ExactlyK(k) => (k, false),
+            InvalidRule::AtLeastK(k) => (k, true),
+        }
+    }
+
+    /// Whether a single ID breaks the rule, by comparing its digits in base `radix`.
+    pub fn is_invalid(self, id: u128, radix: u32) -> bool {
+        // Patterns never start with a zero, and 0 would be the pattern "0" itself
+        id != 0 && self.repeats_in(&to_digits(id, radix))
+    }
+
+    /// Whether a fixed-width ID breaks the rule once zero-padded to `width` digits. An ID with
+    /// more digits than that does not fit the format, so it is never invalid.
+    pub fn is_invalid_padded(self, id: u128, width: u32, radix: u32) -> bool {
+        let digits = to_padded_digits(id, radix, width);
+        digits.len() == width as usize && self.repeats_in(&digits)
+    }
+
+    fn repeats_in(self, digits: &[u8]) -> bool {
+        let len = digits.len();
+        let (k, or_more) = self.repetitions();
+        let k = k as usize;
+
+        let repeats = |times: usize| {
+            len.is_multiple_of(times) && digits[..len / times].repeat(times) == digits
+        };
+        if or_more {
+            (k..=len).any(repeats)
+        } else {
+            k <= len && repeats(k)
+        }
+    }
+
+    /// Sum of every invalid ID in `range`, written in base `radix`, without visiting the IDs
+    /// one by one.
+    pub fn sum<T: Id>(self, range: &IdRange<T>, radix: u32) -> Result<T, SumOverflow> {
+        narrow_sum(self.tally(Span::new(range), radix).sum, self)
+    }
+
+    /// Number of invalid IDs in `range`, also without visiting them.
+    pub fn count<T: Id>(self, range: &IdRange<T>, radix: u32) -> u128 {
+        self.tally(Span::new(range), radix).count
+    }
+
+    /// Sum of every invalid ID across `ranges`, counting IDs in overlapping ranges each time.
+    pub fn total<T: Id>(self, ranges: &[IdRange<T>], radix: u32) -> Result<T, SumOverflow> {
+        let total = ranges.iter().try_fold(0u128, |total, range| {
+            total.checked_add(self.tally(Span::new(range), radix).sum?)
+        });
+        narrow_sum(total, self)
+    }
+
+    /// The invalid IDs in `range` in increasing order, found one at a time as the iterator is
+    /// advanced, so even a range with more IDs than fit in memory can be listed.
+    pub fn invalid_ids<T: Id>(self, range: &IdRange<T>, radix: u32) -> InvalidIds<T> {
+        let span = Span::new(range);
+        InvalidIds {
+            rule: self,
+            radix,
+            len: span.map_or(0, |span| digit_lengths(span, radix).start() - 1),
+            span,
+            streams: Vec::new(),
+            id: PhantomData,
+        }
+    }
+
+    fn tally(self, span: Option<Span>, radix: u32) -> Tally {
+        let (k, or_more) = self.repetitions();
+        let Some(span) = span else {
+            return Tally::EMPTY;
+        };
+        digit_lengths(span, radix).fold(Tally::EMPTY, |total, len| {
+            let tally = if or_more {
+                tally_repeated_at_least(span, len, k, radix)
+            } else if len.is_multiple_of(k) {
+                tally_repeated(span, len, len / k, radix)
+            } else {
+                Tally::EMPTY
+            };
+            total.add(tally)
+        })
+    }
+
+    // Lengths of the patterns whose repetition makes a `len`-digit ID invalid.
+    fn pattern_lengths(self, len: u32) -> Vec<u32> {
+        let (k, or_more) = self.repetitions();
+        if or_more {
+            divisors(len).filter(|&d| d * k <= len).collect()
+        } else if len.is_multiple_of(k) {
+            vec![len / k]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl fmt::Display for InvalidRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidRule::ExactlyTwice => write!(f, "exactly-twice"),
+            InvalidRule::AtLeastTwice => write!(f, "at-least-twice"),
+            InvalidRule::ExactlyK(k) => write!(f, "exactly-{k}"),
+            InvalidRule::AtLeastK(k) => write!(f, "at-least-{k}"),
+        }
+    }
+}
+
+impl FromStr for InvalidRule {
+    type Err = String;
+
+    /// Parses the names printed by `Display`, such as `exactly-twice` or `at-least-3`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "unknown rule {s:?}, expected exactly-twice, at-least-twice, exactly-<k> or at-least-<k>"
+            )
+        };
+        let count = |k: &str| match k.parse::<u32>() {
+            Ok(k) if k > 0 => Ok(k),
+            _ => Err(invalid()),
+        };
+        match s {
+            "exactly-twice" => Ok(InvalidRule::ExactlyTwice),
+            "at-least-twice" => Ok(InvalidRule::AtLeastTwice),
+            _ => {
+                if let Some(k) = s.strip_prefix("exactly-") {
+                    Ok(InvalidRule::ExactlyK(count(k)?))
+                } else if let Some(k) = s.strip_prefix("at-least-") {
+                    Ok(InvalidRule::AtLeastK(count(k)?))
+                } else {
+                    Err(invalid())
+                }
+            }
+        }
+    }
+}
+
+/// Most significant digit first, in base `radix`; zero is the single digit 0.
+pub fn to_digits(id: u128, radix: u32) -> Vec<u8> {
+    let radix = radix as u128;
+    let mut digits = Vec::new();
+    let mut rest = id;
+    loop {
+        digits.push((rest % radix) as u8);
+        rest /= radix;
+        if rest == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    digits
+}
+
+/// Like [`to_digits`], with leading zeros added to make at least `width` digits.
+pub fn to_padded_digits(id: u128, radix: u32, width: u32) -> Vec<u8> {
+    let digits = to_digits(id, radix);
+    let mut padded = vec![0; (width as usize).saturating_sub(digits.len())];
+    padded.extend(digits);
+    padded
+}
+
+/// Writes `id` in base `radix` as the input would, with lowercase letters for digits above 9
+/// and leading zeros up to `width` digits.
+pub fn format_id(id: u128, radix: u32, width: u32) -> String {
+    to_padded_digits(id, radix, width)
+        .into_iter()
+        .map(|digit| char::from_digit(digit as u32, radix).unwrap())
+        .collect()
+}
+
+/// Writes `range` as `FIRST-LAST` the way the input would, with [`format_id`].
+pub fn format_range<T: Id>(range: &IdRange<T>, radix: u32) -> String {
+    format!(
+        "{}-{}",
+        format_id(range.start.into(), radix, range.digits.unwrap_or(0)),
+        format_id(range.end.into(), radix, range.digits.unwrap_or(0))
+    )
+}
+
+/// An invalid ID with the shortest pattern that repeats to form it, so 1111 is the pattern 1
+/// four times even under a rule that only asked for two repeats. In a fixed-width range the
+/// pattern has `width / repeats` digits, counting its leading zeros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidId<T> {
+    pub id: T,
+    pub pattern: T,
+    pub repeats: u32,
+}
+
+impl<T: Id> InvalidId<T> {
+    fn new(id: u128, radix: u32, width: Option<u32>) -> Self {
+        let digits = to_padded_digits(id, radix, width.unwrap_or(0));
+        let len = digits.len();
+        let period = (1..=len)
+            .find(|&d| len.is_multiple_of(d) && digits[..d].repeat(len / d) == digits)
+            .unwrap();
+        let pattern = digits[..period].iter().fold(0u128, |pattern, &digit| {
+            pattern * radix as u128 + digit as u128
+        });
+        // Both are at most `id`, which came from a range of `T`
+        InvalidId {
+            id: narrow(id),
+            pattern: narrow(pattern),
+            repeats: (len / period) as u32,
+        }
+    }
+}
+
+fn narrow<T: Id>(value: u128) -> T {
+    T::try_from(value).ok().unwrap()
+}
+
+/// Iterator returned by [`InvalidRule::invalid_ids`].
+#[derive(Debug, Clone)]
+pub struct InvalidIds<T> {
+    rule: InvalidRule,
+    radix: u32,
+    span: Option<Span>,
+    // Digit length of the IDs the streams are producing
+    len: u32,
+    streams: Vec<Stream>,
+    id: PhantomData<T>,
+}
+
+// The IDs of the current length made by repeating patterns of one length, in increasing order.
+#[derive(Debug, Clone)]
+struct Stream {
+    multiplier: u128,
+    // `None` once the stream has passed `last`
+    next: Option<u128>,
+    last: u128,
+}
+
+impl<T: Id> Iterator for InvalidIds<T> {
+    type Item = InvalidId<T>;
+
+    fn next(&mut self) -> Option<InvalidId<T>> {
+        let span = self.span?;
+        loop {
+            // An ID made from several pattern lengths (111111 from 11 and 111) comes out once
+            let id = |stream: &Stream| stream.next.map(|pattern| pattern * stream.multiplier);
+            if let Some(smallest) = self.streams.iter().filter_map(id).min() {
+                for stream in &mut self.streams {
+                    if id(stream) == Some(smallest) {
+                        stream.next = stream
+                            .next
+                            .filter(|&next| next < stream.last)
+                            .map(|next| next + 1);
+                    }
+                }
+                return Some(InvalidId::new(smallest, self.radix, span.digits));
+            }
+            if self.len >= *digit_lengths(span, self.radix).end() {
+                self.span = None;
+                return None;
+            }
+            self.len += 1;
+            let (len, radix) = (self.len, self.radix);
+            self.streams = self
+                .rule
+                .pattern_lengths(len)
+                .into_iter()
+                .filter_map(|pattern_len| patterns(span, len, pattern_len, radix))
+                .map(|patterns| Stream {
+                    multiplier: patterns.multiplier,
+                    next: Some(patterns.first),
+                    last: patterns.last,
+                })
+                .collect();
+        }
+    }
+}
+
+// The IDs of a range as unsigned values, so the arithmetic below works up to u128::MAX.
+#[derive(Debug, Clone, Copy)]
+struct Span {
+    start: u128,
+    end: u128,
+    digits: Option<u32>,
+}
+
+impl Span {
+    // `None` when the range holds no IDs, including fixed-width ranges of zero digits.
+    fn new<T: Id>(range: &IdRange<T>) -> Option<Self> {
+        let (start, end) = (range.start.into(), range.end.into());
+        (start <= end && range.digits != Some(0)).then_some(Span {
+            start,
+            end,
+            digits: range.digits,
+        })
+    }
+}
+
+// Invalid IDs are never scanned one by one. An ID of `len` digits made of a `pattern_len`-digit
+// pattern repeated is `pattern * multiplier`, where the multiplier is 1 followed by
+// `pattern_len - 1` zeros, repeated `len / pattern_len` times (e.g. 123123 = 123 * 1001, and
+// in base 2, 101101 = 101 * 1001). So the IDs inside a range form an arithmetic series over
+// the patterns, and the cost only depends on the number of digit lengths the range spans.
+// Every step is checked, so a sum too large for a u128 is reported instead of wrapping.
+
+// How many IDs some part of a range holds and their sum, which is `None` past u128::MAX. The
+// count fits unless a fixed-width range holds all 2^128 IDs, where it saturates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Tally {
+    count: u128,
+    sum: Option<u128>,
+}
+
+impl Tally {
+    const EMPTY: Tally = Tally {
+        count: 0,
+        sum: Some(0),
+    };
+
+    fn add(self, other: Tally) -> Tally {
+        Tally {
+            count: self.count.saturating_add(other.count),
+            sum: self
+                .sum
+                .zip(other.sum)
+                .and_then(|(sum, other)| sum.checked_add(other)),
+        }
+    }
+
+    // Only ever removes IDs that `self` includes, so neither part can go negative.
+    fn sub(self, other: Tally) -> Tally {
+        Tally {
+            count: self.count - other.count,
+            sum: self.sum.zip(other.sum).map(|(sum, other)| sum - other),
+        }
+    }
+}
+
+// The `len`-digit IDs in a span made by repeating a `pattern_len`-digit pattern are
+// `pattern * multiplier` for every pattern from `first` to `last`.
+struct Patterns {
+    multiplier: u128,
+    first: u128,
+    last: u128,
+}
+
+fn patterns(span: Span, len: u32, pattern_len: u32, radix: u32) -> Option<Patterns> {
+    let radix = radix as u128;
+    // Without a multiplier every such ID is larger than u128::MAX, so none can be in the span
+    let multiplier = repunit(radix, pattern_len, len / pattern_len)?;
+    // Patterns have no leading zero unless the IDs are zero-padded, and the repeated ID must
+    // fall inside the span
+    let unit = radix.checked_pow(pattern_len - 1);
+    let smallest_pattern = if span.digits.is_some() { 0 } else { unit? };
+    // The largest pattern of the widest u128 IDs is beyond u128 itself, so clamp it
+    let largest_pattern = unit
+        .and_then(|unit| unit.checked_mul(radix))
+        .map_or(u128::MAX, |next| next - 1);
+    let first = smallest_pattern.max(span.start.div_ceil(multiplier));
+    let last = largest_pattern.min(span.end / multiplier);
+    (first <= last).then_some(Patterns {
+        multiplier,
+        first,
+        last,
+    })
+}
+
+// The `len`-digit IDs in `span` that are a `pattern_len`-digit pattern repeated.
+fn tally_repeated(span: Span, len: u32, pattern_len: u32, radix: u32) -> Tally {
+    let Some(Patterns {
+        multiplier,
+        first,
+        last,
+    }) = patterns(span, len, pattern_len, radix)
+    else {
+        return Tally::EMPTY;
+    };
+
+    // first + (first + 1) + ... + last, arranged so no intermediate overflows needlessly
+    let Some(count) = (last - first).checked_add(1) else {
+        return Tally {
+            count: u128::MAX,
+            sum: None,
+        };
+    };
+    let sum = (|| {
+        let triangle = if count.is_multiple_of(2) {
+            (count / 2).checked_mul(count - 1)?
+        } else {
+            count.checked_mul((count - 1) / 2)?
+        };
+        let series = first.checked_mul(count)?.checked_add(triangle)?;
+        multiplier.checked_mul(series)
+    })();
+    Tally { count, sum }
+}
+
+// 1 + radix^pattern_len + radix^(2 * pattern_len) + ... with `repeats` terms.
+fn repunit(radix: u128, pattern_len: u32, repeats: u32) -> Option<u128> {
+    if repeats == 1 {
+        return Some(1);
+    }
+    let shift = radix.checked_pow(pattern_len)?;
+    (0..repeats).try_fold(0u128, |acc, _| acc.checked_mul(shift)?.checked_add(1))
+}
+
+// An ID like 111111 is both 11 repeated three times and 111 repeated twice, so summing every
+// allowed pattern length would count it twice. Instead, split the IDs by their shortest
+// period q. With g(q) the sum of IDs that repeat with period q, the IDs whose shortest period
+// is exactly q sum to h(q) = g(q) - Σ h(e) over the proper divisors e of q. An ID repeats at
+// least k times when its shortest period divides some d <= len / k with d | len, so the total
+// is the sum of h(q) over those q. Each h(q) counts a subset of the answer, so nothing
+// overflows unless the answer itself does. Counts are split the same way.
+fn tally_repeated_at_least(span: Span, len: u32, k: u32, radix: u32) -> Tally {
+    let allowed: Vec<u32> = divisors(len).filter(|&d| d * k <= len).collect();
+    let mut exact: Vec<(u32, Tally)> = Vec::new();
+    for q in divisors(len).filter(|&q| allowed.iter().any(|&d| d.is_multiple_of(q))) {
+        let mut tally = tally_repeated(span, len, q, radix);
+        for &(e, shorter) in &exact {
+            if q.is_multiple_of(e) {
+                tally = tally.sub(shorter);
+            }
+        }
+        exact.push((q, tally));
+    }
+    exact
+        .iter()
+        .fold(Tally::EMPTY, |total, &(_, tally)| total.add(tally))
+}
+
+fn divisors(n: u32) -> impl Iterator<Item = u32> {
+    (1..=n).filter(move |d| n.is_multiple_of(*d))
+}
+
+// Every ID of a fixed-width range has its width, however small its value.
+fn digit_lengths(span: Span, radix: u32) -> RangeInclusive<u32> {
+    match span.digits {
+        Some(width) => width..=width,
+        None => num_digits(span.start.max(1), radix)..=num_digits(span.end, radix),
+    }
+}
+
+fn num_digits(id: u128, radix: u32) -> u32 {
+    id.checked_ilog(radix as u128).unwrap_or(0) + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: [InvalidRule; 8] = [
+        InvalidRule::ExactlyTwice,
+        InvalidRule::AtLeastTwice,
+        InvalidRule::ExactlyK(1),
+        InvalidRule::ExactlyK(3),
+        InvalidRule::ExactlyK(4),
+        InvalidRule::AtLeastK(1),
+        InvalidRule::AtLeastK(3),
+        InvalidRule::AtLeastK(5),
+    ];
+
+    const EXAMPLE: [(u64, u64); 11] = [
+        (11, 22),
+        (95, 115),
+        (998, 1012),
+        (1188511880, 1188511890),
+        (222220, 222224),
+        (1698522, 1698528),
+        (446443, 446449),
+        (38593856, 38593862),
+        (565653, 565659),
+        (824824821, 824824827),
+        (2121212118, 2121212124),
+    ];
+
+    fn ranges<T: Copy>(bounds: &[(T, T)]) -> Vec<IdRange<T>> {
+        bounds
+            .iter()
+            .map(|&(start, end)| IdRange::new(start, end))
+            .collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let ranges = ranges(&EXAMPLE);
+        let total = |rule: InvalidRule| rule.total(&ranges, 10).unwrap();
+        assert_eq!(total(InvalidRule::ExactlyTwice), 1227775554);
+        assert_eq!(total(InvalidRule::AtLeastTwice), 4174379265);
+        assert_eq!(total(InvalidRule::ExactlyK(2)), 1227775554);
+        assert_eq!(total(InvalidRule::AtLeastK(2)), 4174379265);
+    }
+
+    #[test]
+    fn test_is_invalid() {
+        assert!(InvalidRule::ExactlyTwice.is_invalid(6464, 10));
+        assert!(InvalidRule::ExactlyTwice.is_invalid(1111, 10));
+        assert!(!InvalidRule::ExactlyTwice.is_invalid(111, 10));
+        assert!(InvalidRule::AtLeastTwice.is_invalid(111, 10));
+        assert!(InvalidRule::ExactlyK(3).is_invalid(123123123, 10));
+        assert!(!InvalidRule::ExactlyK(3).is_invalid(12341234, 10));
+        assert!(InvalidRule::ExactlyK(3).is_invalid(111111, 10));
+        assert!(InvalidRule::AtLeastK(3).is_invalid(1212121212, 10));
+        assert!(!InvalidRule::AtLeastK(3).is_invalid(123123, 10));
+        // 0b101101 and 0xABAB
+        assert!(InvalidRule::ExactlyTwice.is_invalid(45, 2));
+        assert!(InvalidRule::ExactlyTwice.is_invalid(0xABAB, 16));
+        assert!(!InvalidRule::ExactlyTwice.is_invalid(0xABAB, 10));
+    }
+
+    #[test]
+    fn test_sum_matches_brute_force() {
+        let bounds: Vec<(u64, u64)> = (0..2000)
+            .step_by(37)
+            .flat_map(|start| [(start, start + 150), (start * 53, start * 53 + 9000)])
+            .chain([
+                (0, 0),
+                (1, 9),
+                (5, 4),
+                (99_990, 1_001_100),
+                (1_111_111, 1_111_111),
+                (999_999_000_000, 999_999_999_999),
+            ])
+            .collect();
+        for range in ranges(&bounds) {
+            let range = IdRange {
+                end: range.end.min(range.start + 2_000),
+                ..range
+            };
+            for radix in [2, 3, 10, 36] {
+                for rule in RULES {
+                    let expected: Vec<u64> = (range.start..=range.end)
+                        .filter(|&id| rule.is_invalid(id.into(), radix))
+                        .collect();
+                    let message = format!("{rule} in base {radix} over {range:?}");
+                    assert_eq!(
+                        rule.sum(&range, radix).map(u128::from),
+                        Ok(expected.iter().map(|&id| u128::from(id)).sum()),
+                        "{message}"
+                    );
+                    assert_eq!(
+                        rule.count(&range, radix),
+                        expected.len() as u128,
+                        "{message}"
+                    );
+                    let listed: Vec<u64> = rule
+                        .invalid_ids(&range, radix)
+                        .map(|invalid| invalid.id)
+                        .collect();
+                    assert_eq!(listed, expected, "{message}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_invalid_padded() {
+        assert!(InvalidRule::ExactlyTwice.is_invalid_padded(123000123, 12, 10));
+        assert!(!InvalidRule::ExactlyTwice.is_invalid(123000123, 10));
+        // 0101 is 01 twice once its leading zero counts, and 0000 is 0 four times
+        assert!(InvalidRule::ExactlyTwice.is_invalid_padded(101, 4, 10));
+        assert!(InvalidRule::AtLeastK(4).is_invalid_padded(0, 4, 10));
+        assert!(!InvalidRule::AtLeastTwice.is_invalid_padded(101, 3, 10));
+        // Too wide for the format
+        assert!(!InvalidRule::ExactlyTwice.is_invalid_padded(1212, 2, 10));
+    }
+
+    #[test]
+    fn test_padded_matches_brute_force() {
+        for (radix, width) in [(10, 1), (10, 4), (10, 6), (2, 12), (16, 4)] {
+            let top = (radix as u64).pow(width) - 1;
+            let bounds = [(0, top), (0, 0), (1, 7), (top / 3, top / 2), (top, top)];
+            for (start, end) in bounds {
+                let range = IdRange::padded(start, end.min(start + 20_000), width);
+                for rule in RULES {
+                    let expected: Vec<u64> = (range.start..=range.end)
+                        .filter(|&id| rule.is_invalid_padded(id.into(), width, radix))
+                        .collect();
+                    let message = format!("{rule} in base {radix} over {range:?}");
+                    assert_eq!(
+                        rule.sum(&range, radix).map(u128::from),
+                        Ok(expected.iter().map(|&id| u128::from(id)).sum()),
+                        "{message}"
+                    );
+                    assert_eq!(
+                        rule.count(&range, radix),
+                        expected.len() as u128,
+                        "{message}"
+                    );
+                    let listed: Vec<u64> = rule
+                        .invalid_ids(&range, radix)
+                        .map(|invalid| invalid.id)
+                        .collect();
+                    assert_eq!(listed, expected, "{message}");
+                }
+            }
+        }
+        let range = IdRange::padded(123000000u64, 123999999, 12);
+        let invalid: Vec<_> = InvalidRule::ExactlyTwice.invalid_ids(&range, 10).collect();
+        assert_eq!(
+            invalid,
+            [InvalidId {
+                id: 123000123,
+                pattern: 123,
+                repeats: 2
+            }]
+        );
+        // Every 40-digit string, which is more IDs than a u128 can count
+        let everything = IdRange::padded(0u128, u128::MAX, 40);
+        assert_eq!(InvalidRule::AtLeastK(1).count(&everything, 10), u128::MAX);
+        assert!(InvalidRule::AtLeastK(1).sum(&everything, 10).is_err());
+    }
+
+    #[test]
+    fn test_u128_matches_brute_force() {
+        // Windows beyond u64, including the 38 to 39 digit boundary and the top of u128
+        let starts = [
+            u64::MAX as u128 - 1_000,
+            12_345_678_901_234_567_890_123_456_789_012_345_678_u128 - 1_000,
+            12_341_234_123_412_341_234_123_412_341_234_u128 - 500,
+            10u128.pow(38) - 1_000,
+            u128::MAX - 1_500,
+        ];
+        for start in starts {
+            let range = IdRange::new(start, start.saturating_add(1_500));
+            for radix in [2, 10, 16] {
+                for rule in RULES {
+                    let expected = (range.start..=range.end)
+                        .filter(|&id| rule.is_invalid(id, radix))
+                        .try_fold(0u128, |total, id| total.checked_add(id));
+                    assert_eq!(
+                        rule.sum(&range, radix).ok(),
+                        expected,
+                        "{rule} in base {radix} over {range:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_wide_range() {
+        let range = IdRange::new(1u128, i64::MAX as u128);
+        // Spans every 1 to 19 digit ID, which no scan could finish
+        for radix in [2, 10, 36] {
+            let twice = InvalidRule::ExactlyTwice.sum(&range, radix).unwrap();
+            assert!(twice > 0);
+            assert!(InvalidRule::AtLeastTwice.sum(&range, radix).unwrap() > twice);
+        }
+        // Every ID at all, whose sum is far beyond a u64 but still fits in a u128
+        assert_eq!(
+            InvalidRule::ExactlyK(1).sum(&range, 10),
+            Ok(i64::MAX as u128 * (i64::MAX as u128 + 1) / 2)
+        );
+        let narrow = IdRange::new(1u64, i64::MAX as u64);
+        assert_eq!(
+            InvalidRule::ExactlyK(1).sum(&narrow, 10),
+            Err(SumOverflow::new::<u64>(InvalidRule::ExactlyK(1)))
+        );
+    }
+
+    #[test]
+    fn test_overflow_is_reported() {
+        // 4294942949 is the largest ten-digit doubled ID that fits in a u32, so the sum overflows
+        let range = IdRange::new(4_000_000_000u32, u32::MAX);
+        let overflow = InvalidRule::ExactlyTwice.sum(&range, 10).unwrap_err();
+        assert_eq!(overflow.width, "u32");
+        assert_eq!(
+            overflow.to_string(),
+            "the sum of invalid IDs under exactly-twice does not fit in a u32"
+        );
+        let first = IdRange::new(4_000_000_000u32, 4_000_040_000);
+        assert_eq!(InvalidRule::ExactlyTwice.sum(&first, 10), Ok(4_000_040_000));
+        assert!(
+            InvalidRule::ExactlyTwice
+                .total(&[first, first], 10)
+                .is_err()
+        );
+        // Even u128 runs out when summing every ID near its top
+        let top = IdRange::new(u128::MAX - 10, u128::MAX);
+        assert!(InvalidRule::AtLeastK(1).sum(&top, 10).is_err());
+    }
+
+    #[test]
+    fn test_invalid_ids_are_lazy() {
+        // More IDs than could ever be collected, yet the first few come out at once
+        let range = IdRange::new(1u128, u128::MAX);
+        let first: Vec<_> = InvalidRule::AtLeastTwice
+            .invalid_ids(&range, 10)
+            .take(11)
+            .map(|invalid| (invalid.id, invalid.pattern, invalid.repeats))
+            .collect();
+        assert_eq!(first[0], (11, 1, 2));
+        assert_eq!(first[8], (99, 9, 2));
+        assert_eq!(first[9], (111, 1, 3));
+        assert_eq!(first[10], (222, 2, 3));
+        let last = InvalidRule::ExactlyTwice
+            .invalid_ids(&IdRange::new(u128::MAX - 10u128.pow(19), u128::MAX), 10)
+            .last();
+        assert_eq!(last, None);
+        // 1111 is the pattern 1 four times, even though exactly-twice found it as 11 twice
+        let range = IdRange::new(1111u32, 1111);
+        let invalid = InvalidRule::ExactlyTwice.invalid_ids(&range, 10).next();
+        assert_eq!(
+            invalid,
+            Some(InvalidId {
+                id: 1111,
+                pattern: 1,
+                repeats: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_format_id() {
+        assert_eq!(format_id(0, 10, 0), "0");
+        assert_eq!(format_id(0xabab, 16, 0), "abab");
+        assert_eq!(format_id(45, 2, 0), "101101");
+        assert_eq!(format_id(123000123, 10, 12), "000123000123");
+        assert_eq!(format_id(0, 10, 4), "0000");
+    }
+
+    #[test]
+    fn test_to_digits() {
+        assert_eq!(to_digits(0, 10), [0]);
+        assert_eq!(to_digits(45, 2), [1, 0, 1, 1, 0, 1]);
+        assert_eq!(to_digits(35 * 36 + 1, 36), [35, 1]);
+        assert_eq!(to_digits(u128::MAX, 2).len(), 128);
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for rule in RULES {
+            assert_eq!(rule.to_string().parse::<InvalidRule>(), Ok(rule));
+        }
+        assert_eq!("exactly-2".parse(), Ok(InvalidRule::ExactlyK(2)));
+        assert!("exactly-0".parse::<InvalidRule>().is_err());
+        assert!("twice".parse::<InvalidRule>().is_err());
+    }
+}
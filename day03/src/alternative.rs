@@ -1,33 +1,99 @@
 // Alternative optimized solution for Day 3 Part 2
 // Uses a more efficient stack-based approach for finding the largest subsequence
 
+use day03::bench::{self, Case, Distribution, Strategy};
+use day03::max_joltage;
+use std::env;
 use std::fs;
+use std::io;
+use std::path::Path;
+
+const USAGE: &str = "usage: alternative [--table FILE]
+
+  --table FILE   also write the benchmark comparison table to FILE";
+
+// Batteries turned on by every strategy the benchmark compares
+const K: usize = 12;
+
+// The strategies the benchmark runs and cross-checks; adding one here is all it takes.
+const STRATEGIES: &[Strategy] = &[
+    Strategy {
+        name: "stack (lib)",
+        joltage: |bank| max_joltage(bank, K, 10).ok(),
+    },
+    Strategy {
+        name: "stack (optimized)",
+        joltage: |bank| u64::try_from(find_max_joltage_part2_optimized(bank)).ok(),
+    },
+    Strategy {
+        name: "dp",
+        joltage: |bank| u64::try_from(find_max_joltage_part2_dp(bank)).ok(),
+    },
+];
+
+// Bank lengths and counts for the generated cases, and the timed runs of each
+const BANK_LENGTHS: [usize; 4] = [15, 100, 1_000, 5_000];
+const BANKS_PER_CASE: usize = 10;
+const SAMPLES: usize = 11;
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let mut table_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--table" => {
+                table_path = Some(
+                    args.next()
+                        .ok_or(format!("--table needs a file\n{USAGE}"))?,
+                );
+            }
+            _ => return Err(format!("unknown argument {arg:?}\n{USAGE}").into()),
+        }
+    }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Testing alternative solution for Day 3 Part 2");
 
     // Test with example first
     println!("=== Testing with example ===");
     let test_content = load_input("test_input.txt")?;
     println!("Test Part 1: {}", solve_part1(&test_content));
-    println!("Test Part 2 (optimized): {}", solve_part2_optimized(&test_content));
-    
-    // Test with actual input
-    println!("\n=== Testing with actual input ===");
-    let content = load_input("input.txt")?;
-    println!("Part 1: {}", solve_part1(&content));
-    println!("Part 2 (optimized): {}", solve_part2_optimized(&content));
-    
+    println!(
+        "Test Part 2 (optimized): {}",
+        solve_part2_optimized(&test_content)
+    );
+
+    // Test with actual input, which is not checked in
+    let content = if Path::new("input.txt").exists() {
+        println!("\n=== Testing with actual input ===");
+        let content = load_input("input.txt")?;
+        println!("Part 1: {}", solve_part1(&content));
+        println!("Part 2 (optimized): {}", solve_part2_optimized(&content));
+        Some(content)
+    } else {
+        println!("\nNo input.txt, so only generated banks are benchmarked");
+        None
+    };
+
     // Benchmark approaches
     println!("\n=== Benchmarking ===");
-    benchmark_approaches(&content);
-    
+    benchmark_approaches(content.as_deref(), table_path.as_deref())?;
+
     Ok(())
 }
 
 fn load_input(file_path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     let content = fs::read_to_string(file_path)?;
-    let lines: Vec<String> = content.lines().map(|line| line.trim().to_string()).collect();
+    let lines: Vec<String> = content
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect();
     Ok(lines)
 }
 
@@ -45,27 +111,27 @@ pub fn solve_part1(banks: &[String]) -> i32 {
 
 fn find_max_joltage_part1(line: &str) -> i32 {
     let digits: Vec<char> = line.chars().collect();
-    
+
     if digits.len() < 2 {
         return 0;
     }
-    
+
     let mut max_joltage = 0;
-    
+
     for i in 0..(digits.len() - 1) {
         let mut max_after = '0';
-        for j in (i + 1)..digits.len() {
-            if digits[j] > max_after {
-                max_after = digits[j];
+        for &digit in &digits[(i + 1)..] {
+            if digit > max_after {
+                max_after = digit;
             }
         }
-        
+
         let joltage = format!("{}{}", digits[i], max_after).parse().unwrap_or(0);
         if joltage > max_joltage {
             max_joltage = joltage;
         }
     }
-    
+
     max_joltage
 }
 
@@ -93,21 +159,22 @@ fn find_max_joltage_part2_optimized(line: &str) -> i64 {
     // Pop smaller digits when we find a larger one, as long as we have enough digits left
     let mut stack = Vec::new();
     let to_select = 12;
-    
+
     for (i, &digit) in digits.iter().enumerate() {
         // While we can pop (stack not empty, current digit is larger, and we have enough digits left)
-        while !stack.is_empty() 
-            && digit > *stack.last().unwrap() 
-            && (stack.len() - 1 + (n - i) >= to_select) {
+        while !stack.is_empty()
+            && digit > *stack.last().unwrap()
+            && (stack.len() - 1 + (n - i) >= to_select)
+        {
             stack.pop();
         }
-        
+
         // Push current digit if we still need more
         if stack.len() < to_select {
             stack.push(digit);
         }
     }
-    
+
     // Convert stack to number
     let joltage_str: String = stack.into_iter().collect();
     joltage_str.parse().unwrap_or(0)
@@ -136,16 +203,16 @@ fn find_max_joltage_part2_dp(line: &str) -> i64 {
 
     // dp[i][j] = largest number using first i digits with j digits selected
     let mut dp = vec![vec![String::new(); k + 1]; n + 1];
-    
+
     for i in 1..=n {
         for j in 1..=k.min(i) {
             // Option 1: don't take current digit
             let option1 = dp[i - 1][j].clone();
-            
+
             // Option 2: take current digit
             let mut option2 = dp[i - 1][j - 1].clone();
             option2.push(digits[i - 1]);
-            
+
             // Choose the better option (lexicographically larger)
             if option2.len() == j && (option1.is_empty() || option2 > option1) {
                 dp[i][j] = option2;
@@ -154,27 +221,47 @@ fn find_max_joltage_part2_dp(line: &str) -> i64 {
             }
         }
     }
-    
+
     dp[n][k].parse().unwrap_or(0)
 }
 
-// Benchmark function to compare approaches
-pub fn benchmark_approaches(banks: &[String]) {
-    use std::time::Instant;
-    
-    println!("Benchmarking approaches...");
-    
-    // Test optimized approach
-    let start = Instant::now();
-    let result_opt = solve_part2_optimized(banks);
-    let duration_opt = start.elapsed();
-    
-    // Test DP approach
-    let start = Instant::now();
-    let result_dp = solve_part2_dp(banks);
-    let duration_dp = start.elapsed();
-    
-    println!("Optimized approach: {} (took {:?})", result_opt, duration_opt);
-    println!("DP approach: {} (took {:?})", result_dp, duration_dp);
-    println!("Results match: {}", result_opt == result_dp);
-}
\ No newline at end of file
+// Runs every registered strategy on generated banks and on the input if there is one, prints
+// how long each took, and fails if any two disagree on a bank.
+pub fn benchmark_approaches(
+    banks: Option<&[String]>,
+    table_path: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cases = Vec::new();
+    for (seed, distribution) in Distribution::ALL.into_iter().enumerate() {
+        for len in BANK_LENGTHS {
+            cases.push(Case {
+                name: format!("{distribution} x{len}"),
+                banks: distribution.banks(len, BANKS_PER_CASE, seed as u64),
+            });
+        }
+    }
+    if let Some(banks) = banks {
+        cases.push(Case {
+            name: "input.txt".to_string(),
+            banks: banks.to_vec(),
+        });
+    }
+
+    println!(
+        "Benchmarking {} strategies with k={K}, {SAMPLES} runs per case...",
+        STRATEGIES.len()
+    );
+    let comparison = bench::compare(STRATEGIES, &cases, SAMPLES);
+    bench::write_table(&mut io::stdout().lock(), &comparison)?;
+    if let Some(path) = table_path {
+        bench::write_table(&mut fs::File::create(path)?, &comparison)?;
+    }
+
+    match comparison.mismatches.len() {
+        0 => {
+            println!("All strategies agree on every bank");
+            Ok(())
+        }
+        mismatches => Err(format!("strategies disagree on {mismatches} banks").into()),
+    }
+}
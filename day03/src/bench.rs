@@ -0,0 +1,271 @@
+// A harness that times the joltage strategies against each other on generated banks and checks
+// that they all agree.
+
+use crate::rng::Rng;
+use std::fmt;
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// One way of finding a bank's largest joltage, for a number of batteries it fixes itself.
+#[derive(Debug, Clone, Copy)]
+pub struct Strategy {
+    pub name: &'static str,
+    /// The joltage of a bank, or `None` if the strategy cannot handle it.
+    pub joltage: fn(&str) -> Option<u64>,
+}
+
+/// How the battery labels of a generated bank are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Distribution {
+    /// Every digit from 1 to 9 equally likely.
+    Uniform,
+    /// Only 1, 2 and 3, so ties are everywhere.
+    FewDigits,
+    /// Rising runs, so nearly every battery replaces the ones before it.
+    Ascending,
+    /// Mostly 1s with a rare larger digit, like the banks in the puzzle's example.
+    MostlyOnes,
+}
+
+impl Distribution {
+    pub const ALL: [Distribution; 4] = [
+        Distribution::Uniform,
+        Distribution::FewDigits,
+        Distribution::Ascending,
+        Distribution::MostlyOnes,
+    ];
+
+    /// `count` banks of `len` batteries each, the same for the same `seed`.
+    pub fn banks(self, len: usize, count: usize, seed: u64) -> Vec<String> {
+        let mut rng = Rng::new(seed);
+        (0..count)
+            .map(|_| {
+                (0..len)
+                    .map(|position| char::from(b'0' + self.digit(&mut rng, position)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn digit(self, rng: &mut Rng, position: usize) -> u8 {
+        match self {
+            Distribution::Uniform => 1 + rng.below(9) as u8,
+            Distribution::FewDigits => 1 + rng.below(3) as u8,
+            Distribution::Ascending => 1 + (position % 9) as u8,
+            Distribution::MostlyOnes if rng.below(20) == 0 => 2 + rng.below(8) as u8,
+            Distribution::MostlyOnes => 1,
+        }
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Distribution::Uniform => "uniform",
+            Distribution::FewDigits => "few digits",
+            Distribution::Ascending => "ascending",
+            Distribution::MostlyOnes => "mostly ones",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A named set of banks that every strategy is run on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Case {
+    pub name: String,
+    pub banks: Vec<String>,
+}
+
+/// How long one strategy took over all the banks of one case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timing {
+    pub case: String,
+    pub strategy: &'static str,
+    pub median: Duration,
+    pub p95: Duration,
+}
+
+/// A bank the strategies disagree on, with every strategy's answer in registration order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub case: String,
+    pub bank: String,
+    pub answers: Vec<(&'static str, Option<u64>)>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: strategies disagree on {}:", self.case, self.bank)?;
+        for (strategy, answer) in &self.answers {
+            match answer {
+                Some(joltage) => write!(f, " {strategy}={joltage}")?,
+                None => write!(f, " {strategy}=none")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comparison {
+    pub timings: Vec<Timing>,
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// Checks that the strategies agree on every bank, then times each of them `samples` times
+/// over every case.
+pub fn compare(strategies: &[Strategy], cases: &[Case], samples: usize) -> Comparison {
+    let mut comparison = Comparison::default();
+    for case in cases {
+        for bank in &case.banks {
+            let answers: Vec<(&'static str, Option<u64>)> = strategies
+                .iter()
+                .map(|strategy| (strategy.name, (strategy.joltage)(bank)))
+                .collect();
+            if answers.windows(2).any(|pair| pair[0].1 != pair[1].1) {
+                comparison.mismatches.push(Mismatch {
+                    case: case.name.clone(),
+                    bank: bank.clone(),
+                    answers,
+                });
+            }
+        }
+
+        for strategy in strategies {
+            let mut times: Vec<Duration> = (0..samples)
+                .map(|_| {
+                    let start = Instant::now();
+                    for bank in &case.banks {
+                        black_box((strategy.joltage)(black_box(bank)));
+                    }
+                    start.elapsed()
+                })
+                .collect();
+            times.sort();
+            comparison.timings.push(Timing {
+                case: case.name.clone(),
+                strategy: strategy.name,
+                median: percentile(&times, 50),
+                p95: percentile(&times, 95),
+            });
+        }
+    }
+    comparison
+}
+
+// The nearest-rank percentile of sorted samples, or zero if there are none.
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied().unwrap_or_default()
+}
+
+/// Writes one row per case and strategy, and the disagreements after the table.
+pub fn write_table(out: &mut impl Write, comparison: &Comparison) -> io::Result<()> {
+    let case_width = comparison
+        .timings
+        .iter()
+        .map(|timing| timing.case.len())
+        .chain(["case".len()])
+        .max()
+        .unwrap_or_default();
+    let strategy_width = comparison
+        .timings
+        .iter()
+        .map(|timing| timing.strategy.len())
+        .chain(["strategy".len()])
+        .max()
+        .unwrap_or_default();
+
+    writeln!(
+        out,
+        "{:case_width$}  {:strategy_width$}  {:>12}  {:>12}",
+        "case", "strategy", "median", "p95"
+    )?;
+    for timing in &comparison.timings {
+        writeln!(
+            out,
+            "{:case_width$}  {:strategy_width$}  {:>12}  {:>12}",
+            timing.case,
+            timing.strategy,
+            format!("{:.1?}", timing.median),
+            format!("{:.1?}", timing.p95)
+        )?;
+    }
+    for mismatch in &comparison.mismatches {
+        writeln!(out, "{mismatch}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::max_joltage;
+
+    const STRATEGIES: [Strategy; 2] = [
+        Strategy {
+            name: "stack",
+            joltage: |bank| max_joltage(bank, 2, 10).ok(),
+        },
+        Strategy {
+            name: "first two",
+            joltage: |bank| bank.get(..2)?.parse().ok(),
+        },
+    ];
+
+    #[test]
+    fn test_generated_banks() {
+        for distribution in Distribution::ALL {
+            let banks = distribution.banks(50, 3, 7);
+            assert_eq!(banks, distribution.banks(50, 3, 7));
+            assert_eq!(banks.len(), 3);
+            for bank in banks {
+                assert_eq!(bank.len(), 50);
+                assert!(!bank.contains('0'), "{bank}");
+                if distribution == Distribution::FewDigits {
+                    assert!(bank.chars().all(|c| c <= '3'), "{bank}");
+                }
+            }
+        }
+        assert!(Distribution::Ascending.banks(12, 1, 0)[0].starts_with("123456789123"));
+    }
+
+    #[test]
+    fn test_compare_reports_disagreements() {
+        let cases = [Case {
+            name: "example".to_string(),
+            banks: vec!["987654321111111".to_string(), "811111111111119".to_string()],
+        }];
+        let comparison = compare(&STRATEGIES, &cases, 3);
+        assert_eq!(comparison.timings.len(), 2);
+        assert_eq!(
+            comparison.mismatches,
+            [Mismatch {
+                case: "example".to_string(),
+                bank: "811111111111119".to_string(),
+                answers: vec![("stack", Some(89)), ("first two", Some(81))],
+            }]
+        );
+        assert_eq!(
+            comparison.mismatches[0].to_string(),
+            "example: strategies disagree on 811111111111119: stack=89 first two=81"
+        );
+
+        let mut table = Vec::new();
+        write_table(&mut table, &comparison).unwrap();
+        let table = String::from_utf8(table).unwrap();
+        assert!(table.starts_with("case     strategy         median           p95\n"));
+        assert_eq!(table.lines().count(), 4);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let times: Vec<Duration> = (1..=20).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&times, 50), Duration::from_millis(10));
+        assert_eq!(percentile(&times, 95), Duration::from_millis(19));
+        assert_eq!(percentile(&times[..1], 95), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50), Duration::ZERO);
+    }
+}
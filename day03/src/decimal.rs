@@ -0,0 +1,212 @@
+// An unsigned decimal big integer, for joltages with more digits than a u64 holds.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::AddAssign;
+
+// Each limb holds nine decimal digits, so printing never needs a division
+const LIMB: u32 = 1_000_000_000;
+const LIMB_DIGITS: usize = 9;
+
+/// A non-negative integer of any size, stored as base 10^9 limbs, least significant first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    // No trailing zero limbs, so zero is empty and equal values have equal limbs
+    limbs: Vec<u32>,
+}
+
+impl Decimal {
+    /// The number written by `digits` in base `radix`, most significant first. Every digit
+    /// must be below `radix`, which must be from 2 to 36.
+    pub fn from_digits(digits: &[u8], radix: u32) -> Decimal {
+        debug_assert!(digits.iter().all(|&digit| u32::from(digit) < radix));
+        if radix != 10 {
+            let mut number = Decimal::default();
+            for &digit in digits {
+                number.mul_add(radix, digit.into());
+            }
+            return number;
+        }
+
+        // Decimal digits fill whole limbs directly, nine at a time
+        let mut limbs: Vec<u32> = digits
+            .rchunks(LIMB_DIGITS)
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .fold(0, |limb, &digit| limb * 10 + u32::from(digit))
+            })
+            .collect();
+        trim(&mut limbs);
+        Decimal { limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    // self = self * factor + addend, for a factor and addend below one limb
+    fn mul_add(&mut self, factor: u32, addend: u32) {
+        let mut carry = u64::from(addend);
+        for limb in &mut self.limbs {
+            let product = u64::from(*limb) * u64::from(factor) + carry;
+            *limb = (product % u64::from(LIMB)) as u32;
+            carry = product / u64::from(LIMB);
+        }
+        while carry > 0 {
+            self.limbs.push((carry % u64::from(LIMB)) as u32);
+            carry /= u64::from(LIMB);
+        }
+    }
+}
+
+fn trim(limbs: &mut Vec<u32>) {
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+}
+
+impl From<u128> for Decimal {
+    fn from(mut value: u128) -> Decimal {
+        let mut limbs = Vec::new();
+        while value > 0 {
+            limbs.push((value % u128::from(LIMB)) as u32);
+            value /= u128::from(LIMB);
+        }
+        Decimal { limbs }
+    }
+}
+
+impl From<u64> for Decimal {
+    fn from(value: u64) -> Decimal {
+        Decimal::from(u128::from(value))
+    }
+}
+
+impl AddAssign<&Decimal> for Decimal {
+    fn add_assign(&mut self, other: &Decimal) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+        let mut carry = 0;
+        for (index, limb) in self.limbs.iter_mut().enumerate() {
+            let sum = *limb + other.limbs.get(index).copied().unwrap_or(0) + carry;
+            (*limb, carry) = if sum >= LIMB {
+                (sum - LIMB, 1)
+            } else {
+                (sum, 0)
+            };
+            if carry == 0 && index >= other.limbs.len() {
+                break;
+            }
+        }
+        if carry > 0 {
+            self.limbs.push(carry);
+        }
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Decimal) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Decimal) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut limbs = self.limbs.iter().rev();
+        match limbs.next() {
+            None => write!(f, "0"),
+            Some(top) => {
+                write!(f, "{top}")?;
+                limbs.try_for_each(|limb| write!(f, "{limb:09}"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digits(text: &str) -> Vec<u8> {
+        text.bytes().map(|byte| byte - b'0').collect()
+    }
+
+    #[test]
+    fn test_from_digits() {
+        for text in [
+            "1",
+            "987654321",
+            "1000000000",
+            "123456789012345678901234567890",
+        ] {
+            assert_eq!(Decimal::from_digits(&digits(text), 10).to_string(), text);
+        }
+        assert_eq!(
+            Decimal::from_digits(&digits("000000000000042"), 10).to_string(),
+            "42"
+        );
+        assert!(Decimal::from_digits(&digits("0000"), 10).is_zero());
+        assert_eq!(Decimal::from_digits(&[], 10), Decimal::default());
+        assert_eq!(Decimal::default().to_string(), "0");
+    }
+
+    #[test]
+    fn test_other_bases() {
+        assert_eq!(
+            Decimal::from_digits(&[3, 10, 9, 15], 16),
+            Decimal::from(0x3a9fu64)
+        );
+        assert_eq!(Decimal::from_digits(&[1; 64], 2), Decimal::from(u64::MAX));
+        assert_eq!(
+            Decimal::from_digits(&[35; 24], 36).to_string(),
+            ((0..24).fold(1u128, |power, _| power * 36) - 1).to_string()
+        );
+        assert!(Decimal::from_digits(&[0, 0], 7).is_zero());
+    }
+
+    #[test]
+    fn test_matches_native_integers() {
+        let values = [0, 1, 999_999_999, 1_000_000_000, u64::MAX.into(), u128::MAX];
+        for &value in &values {
+            assert_eq!(Decimal::from(value).to_string(), value.to_string());
+            assert_eq!(
+                Decimal::from_digits(&digits(&value.to_string()), 10),
+                Decimal::from(value)
+            );
+            for &other in &values {
+                if let Some(sum) = value.checked_add(other) {
+                    let mut total = Decimal::from(value);
+                    total += &Decimal::from(other);
+                    assert_eq!(total, Decimal::from(sum), "{value} + {other}");
+                }
+                assert_eq!(
+                    Decimal::from(value).cmp(&Decimal::from(other)),
+                    value.cmp(&other)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_carries_past_u128() {
+        let mut total = Decimal::from(u128::MAX);
+        total += &Decimal::from(u128::MAX);
+        assert_eq!(total.to_string(), "680564733841876926926749214863536422910");
+
+        let mut nines = Decimal::from_digits(&[9; 40], 10);
+        nines += &Decimal::from(1u64);
+        assert_eq!(nines.to_string(), format!("1{}", "0".repeat(40)));
+        assert!(nines > Decimal::from(u128::MAX));
+    }
+}
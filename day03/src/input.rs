@@ -0,0 +1,236 @@
+// Parsing of the bank list: one bank of digit-labelled batteries per line, optionally after a
+// header with its settings.
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+/// A bank of batteries and the settings its header gave it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bank {
+    /// The line of the input the bank is on, counted from 1.
+    pub line: usize,
+    pub batteries: String,
+    /// How many batteries to turn on, from `k=`. Without it, each part decides.
+    pub k: Option<usize>,
+    /// The base of the battery labels, from `base=`, or 10.
+    pub radix: u32,
+}
+
+impl Bank {
+    /// A bank of decimal batteries with no header.
+    pub fn new(line: usize, batteries: &str) -> Bank {
+        Bank {
+            line,
+            batteries: batteries.to_string(),
+            k: None,
+            radix: 10,
+        }
+    }
+}
+
+/// A line that is not a valid bank. Lines and columns count from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputError {
+    pub line: usize,
+    pub column: usize,
+    pub reason: Reason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reason {
+    /// A character that is not a digit in the bank's base.
+    Battery { found: char, radix: u32 },
+    /// A header entry other than `k=N` or `base=B` with B from 2 to 36.
+    Setting(String),
+    /// A header that sets the same thing twice.
+    Repeated(String),
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.reason {
+            Reason::Battery { found, radix } => {
+                write!(f, "expected a base {radix} battery digit, found {found:?}")
+            }
+            Reason::Setting(setting) => write!(
+                f,
+                "unknown setting {setting:?}, expected k=N or base=B with B from 2 to 36"
+            ),
+            Reason::Repeated(setting) => write!(f, "{setting} is set more than once"),
+        }
+    }
+}
+
+impl Error for InputError {}
+
+/// Reads the banks in the file `input`; see [`parse_banks`] for the format.
+pub fn load_input(input: &str) -> Result<Vec<Bank>, Box<dyn Error>> {
+    let content = fs::read_to_string(input)?;
+    Ok(parse_banks(&content)?)
+}
+
+/// Parses one bank per line, each made only of battery digits.
+///
+/// A line may start with a header such as `k=5 base=16:` giving the number of batteries to
+/// turn on and the base of the labels, with either setting left out. Blank lines are skipped
+/// and a `\r` before the newline is allowed, but any other character in a bank, spaces
+/// included, is an error.
+pub fn parse_banks(text: &str) -> Result<Vec<Bank>, InputError> {
+    let mut banks = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let error = |column: usize, reason| InputError {
+            line: index + 1,
+            column: column + 1,
+            reason,
+        };
+
+        let mut bank = Bank::new(index + 1, "");
+        let mut batteries = line;
+        let mut batteries_column = 0;
+        if let Some((header, rest)) = line.split_once(':') {
+            (bank.k, bank.radix) =
+                parse_header(header).map_err(|(at, reason)| error(at, reason))?;
+            batteries = rest.trim_start();
+            batteries_column = line.len() - batteries.len();
+        }
+
+        if let Some((column, found)) = batteries
+            .char_indices()
+            .find(|(_, c)| !c.is_digit(bank.radix))
+        {
+            // Columns count characters, not bytes
+            let column = line[..batteries_column + column].chars().count();
+            let radix = bank.radix;
+            return Err(error(column, Reason::Battery { found, radix }));
+        }
+        bank.batteries = batteries.to_string();
+        banks.push(bank);
+    }
+    Ok(banks)
+}
+
+// The k and base set by a header, or the 0-based column of the bad setting and what is wrong.
+pub(crate) fn parse_header(header: &str) -> Result<(Option<usize>, u32), (usize, Reason)> {
+    let mut k = None;
+    let mut radix = None;
+    for setting in header.split_whitespace() {
+        let at = setting.as_ptr() as usize - header.as_ptr() as usize;
+        let at = header[..at].chars().count();
+        let unknown = || (at, Reason::Setting(setting.to_string()));
+        match setting.split_once('=').ok_or_else(unknown)? {
+            ("k", value) => {
+                let value = value.parse().map_err(|_| unknown())?;
+                if k.replace(value).is_some() {
+                    return Err((at, Reason::Repeated("k".to_string())));
+                }
+            }
+            ("base", value) => {
+                let value = value
+                    .parse()
+                    .ok()
+                    .filter(|radix| (2..=36).contains(radix))
+                    .ok_or_else(unknown)?;
+                if radix.replace(value).is_some() {
+                    return Err((at, Reason::Repeated("base".to_string())));
+                }
+            }
+            _ => return Err(unknown()),
+        }
+    }
+    Ok((k, radix.unwrap_or(10)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_puzzle_format() {
+        assert_eq!(
+            parse_banks("987654321111111\n811111111111119\r\n\n234\n"),
+            Ok(vec![
+                Bank::new(1, "987654321111111"),
+                Bank::new(2, "811111111111119"),
+                Bank::new(4, "234")
+            ])
+        );
+        assert_eq!(parse_banks(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_headers() {
+        let banks = parse_banks("k=5 base=16: 3A9Fb0c\nbase=2:1011\n  k=3 :987\n:12\n").unwrap();
+        assert_eq!(
+            banks,
+            [
+                Bank {
+                    line: 1,
+                    batteries: "3A9Fb0c".to_string(),
+                    k: Some(5),
+                    radix: 16
+                },
+                Bank {
+                    line: 2,
+                    batteries: "1011".to_string(),
+                    k: None,
+                    radix: 2
+                },
+                Bank {
+                    line: 3,
+                    batteries: "987".to_string(),
+                    k: Some(3),
+                    radix: 10
+                },
+                Bank::new(4, "12"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_point_at_the_character() {
+        let error = parse_banks("12345\n\n987a321\n").unwrap_err();
+        assert_eq!(
+            error,
+            InputError {
+                line: 3,
+                column: 4,
+                reason: Reason::Battery {
+                    found: 'a',
+                    radix: 10
+                }
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "line 3, column 4: expected a base 10 battery digit, found 'a'"
+        );
+        assert_eq!(parse_banks("12 34").unwrap_err().column, 3);
+        assert_eq!(parse_banks(" 1234").unwrap_err().column, 1);
+        let error = parse_banks("1²34").unwrap_err();
+        assert_eq!(error.column, 2);
+        assert!(matches!(error.reason, Reason::Battery { found: '²', .. }));
+    }
+
+    #[test]
+    fn test_header_errors() {
+        let column = |text| parse_banks(text).unwrap_err().column;
+        assert_eq!(column("base=8: 1238"), 12);
+        assert_eq!(column("k=2 base=16: 12g"), 16);
+        assert_eq!(column("k=2  n=3: 12"), 6);
+        assert_eq!(column("k=x: 12"), 1);
+        assert_eq!(column("base=37: 12"), 1);
+
+        let error = parse_banks("1\nk=2 k=3: 12").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 5));
+        assert_eq!(error.reason, Reason::Repeated("k".to_string()));
+        assert_eq!(
+            parse_banks("base=1: 0").unwrap_err().to_string(),
+            "line 1, column 1: unknown setting \"base=1\", expected k=N or base=B with B from 2 to 36"
+        );
+    }
+}
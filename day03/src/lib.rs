@@ -0,0 +1,356 @@
+// Shared code for the Day 3 binaries: picking the batteries that give a bank its largest
+// joltage.
+
+pub mod bench;
+pub mod decimal;
+pub mod input;
+mod rng;
+pub mod stream;
+
+pub use decimal::Decimal;
+pub use input::{Bank, InputError, load_input, parse_banks};
+pub use stream::{BankReader, StreamError, StreamedBank};
+
+use std::error::Error;
+use std::fmt;
+
+/// The most batteries whose joltage always fits in a u64 when labelled in base `radix`; past
+/// it, use [`max_joltage_decimal`]. That is 19 for decimal labels.
+pub fn native_digits(radix: u32) -> usize {
+    let radix = u128::from(radix.max(2));
+    let mut digits = 0;
+    let mut largest = radix;
+    while largest <= 1 << 64 {
+        digits += 1;
+        largest *= radix;
+    }
+    digits
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JoltageError {
+    /// The bank has fewer than `k` batteries to turn on.
+    TooShort { len: usize, k: usize },
+    /// A battery that is not labelled with a digit in the bank's base, at a 0-based position.
+    InvalidBattery {
+        position: usize,
+        found: char,
+        radix: u32,
+    },
+    /// Battery labels can only be read in bases 2 to 36.
+    UnsupportedBase { radix: u32 },
+    /// `k` digits can make a joltage too large for a u64; see [`max_joltage_decimal`].
+    TooManyDigits { k: usize },
+}
+
+impl fmt::Display for JoltageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JoltageError::TooShort { len, k } => {
+                write!(f, "cannot turn on {k} batteries in a bank of {len}")
+            }
+            JoltageError::InvalidBattery {
+                position,
+                found,
+                radix,
+            } => write!(
+                f,
+                "battery {position} is labelled {found:?}, not a base {radix} digit"
+            ),
+            JoltageError::UnsupportedBase { radix } => {
+                write!(f, "battery labels cannot be read in base {radix}")
+            }
+            JoltageError::TooManyDigits { k } => {
+                write!(f, "a joltage of {k} digits does not fit in a u64")
+            }
+        }
+    }
+}
+
+impl Error for JoltageError {}
+
+/// A [`JoltageError`] in the bank on a line of the input, counted from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BankError {
+    pub line: usize,
+    pub error: JoltageError,
+}
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl Error for BankError {}
+
+/// Largest joltage from turning on exactly `k` batteries of `bank`, keeping their order. The
+/// batteries are labelled with digits in base `radix`, and so is the joltage they make.
+pub fn max_joltage(bank: &str, k: usize, radix: u32) -> Result<u64, JoltageError> {
+    choose(bank, k, radix)?
+        .iter()
+        .try_fold(0u64, |joltage, &(_, digit)| {
+            joltage.checked_mul(radix.into())?.checked_add(digit.into())
+        })
+        .ok_or(JoltageError::TooManyDigits { k })
+}
+
+/// [`max_joltage`] for any `k`, as an exact big integer.
+pub fn max_joltage_decimal(bank: &str, k: usize, radix: u32) -> Result<Decimal, JoltageError> {
+    let digits: Vec<u8> = choose(bank, k, radix)?
+        .into_iter()
+        .map(|(_, digit)| digit)
+        .collect();
+    Ok(Decimal::from_digits(&digits, radix))
+}
+
+/// 0-based positions, in bank order, of the `k` batteries that [`max_joltage`] turns on.
+pub fn select_batteries(bank: &str, k: usize, radix: u32) -> Result<Vec<usize>, JoltageError> {
+    Ok(choose(bank, k, radix)?
+        .into_iter()
+        .map(|(position, _)| position)
+        .collect())
+}
+
+// The chosen batteries as (position, digit) pairs.
+//
+// This is the lexicographically largest subsequence of length `k`. A monotonic stack finds it
+// in one pass: a battery replaces smaller ones before it while enough batteries remain to
+// still reach `k`, so the whole bank takes O(n) however large `k` is.
+fn choose(bank: &str, k: usize, radix: u32) -> Result<Vec<(usize, u8)>, JoltageError> {
+    if !(2..=36).contains(&radix) {
+        return Err(JoltageError::UnsupportedBase { radix });
+    }
+    let len = bank.chars().count();
+    if len < k {
+        return Err(JoltageError::TooShort { len, k });
+    }
+
+    let mut stack: Vec<(usize, u8)> = Vec::with_capacity(k);
+    let mut drops = len - k;
+    for (position, battery) in bank.chars().enumerate() {
+        let digit = battery
+            .to_digit(radix)
+            .ok_or(JoltageError::InvalidBattery {
+                position,
+                found: battery,
+                radix,
+            })? as u8;
+        while drops > 0 && stack.last().is_some_and(|&(_, top)| top < digit) {
+            stack.pop();
+            drops -= 1;
+        }
+        stack.push((position, digit));
+    }
+    // Whatever was never dropped sits at the end, and is the smallest choice to skip
+    stack.truncate(k);
+    Ok(stack)
+}
+
+/// How [`render`] marks the batteries that are turned on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    /// Bold green, for a terminal.
+    Color,
+    /// Square brackets around every run of them, for plain text.
+    Brackets,
+}
+
+/// The bank with the batteries at `indices` highlighted, as in `[98]7654321111111`.
+pub fn render(bank: &str, indices: &[usize], highlight: Highlight) -> String {
+    let (open, close) = match highlight {
+        Highlight::Color => ("\x1b[1;32m", "\x1b[0m"),
+        Highlight::Brackets => ("[", "]"),
+    };
+    let mut rendered = String::with_capacity(bank.len() + 2 * indices.len());
+    let mut selected = indices.iter().peekable();
+    let mut lit = false;
+    for (position, battery) in bank.chars().enumerate() {
+        let on = selected.next_if_eq(&&position).is_some();
+        if on != lit {
+            rendered.push_str(if on { open } else { close });
+            lit = on;
+        }
+        rendered.push(battery);
+    }
+    if lit {
+        rendered.push_str(close);
+    }
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+
+    const EXAMPLE: [&str; 4] = [
+        "987654321111111",
+        "811111111111119",
+        "234234234234278",
+        "818181911112111",
+    ];
+
+    #[test]
+    fn test_example() {
+        let joltages = |k| -> Vec<u64> {
+            EXAMPLE
+                .iter()
+                .map(|bank| max_joltage(bank, k, 10).unwrap())
+                .collect()
+        };
+        assert_eq!(joltages(2), [98, 89, 78, 92]);
+        assert_eq!(
+            joltages(12),
+            [987654321111, 811111111119, 434234234278, 888911112111]
+        );
+    }
+
+    // Tries every way of turning on k of the batteries.
+    fn brute_force(bank: &str, k: usize, radix: u32) -> u64 {
+        let digits: Vec<u64> = bank
+            .chars()
+            .map(|c| c.to_digit(radix).unwrap().into())
+            .collect();
+        (0u32..1 << digits.len())
+            .filter(|mask| mask.count_ones() as usize == k)
+            .map(|mask| {
+                (0..digits.len())
+                    .filter(|&i| mask & 1 << i != 0)
+                    .fold(0, |joltage, i| joltage * u64::from(radix) + digits[i])
+            })
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Rng::new(0x2545_f491);
+        for (radix, labels) in [(10, "12345678"), (16, "19aF"), (2, "01")] {
+            for len in 0..=12 {
+                for _ in 0..20 {
+                    // Few distinct digits, so ties and runs are common
+                    let bank: String = (0..len)
+                        .map(|_| {
+                            let label = rng.below(labels.len() as u64) as usize;
+                            char::from(labels.as_bytes()[label])
+                        })
+                        .collect();
+                    for k in 0..=len {
+                        assert_eq!(
+                            max_joltage(&bank, k, radix),
+                            Ok(brute_force(&bank, k, radix)),
+                            "{bank} k={k} base={radix}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            max_joltage("12345", 12, 10),
+            Err(JoltageError::TooShort { len: 5, k: 12 })
+        );
+        assert_eq!(
+            max_joltage("12x45", 2, 10),
+            Err(JoltageError::InvalidBattery {
+                position: 2,
+                found: 'x',
+                radix: 10
+            })
+        );
+        assert_eq!(
+            max_joltage("1A", 2, 10).unwrap_err().to_string(),
+            "battery 1 is labelled 'A', not a base 10 digit"
+        );
+        assert_eq!(
+            max_joltage("12", 2, 37),
+            Err(JoltageError::UnsupportedBase { radix: 37 })
+        );
+        assert_eq!(
+            max_joltage(&"9".repeat(25), 20, 10),
+            Err(JoltageError::TooManyDigits { k: 20 })
+        );
+        assert_eq!(
+            max_joltage(&"9".repeat(25), 19, 10),
+            Ok(9_999_999_999_999_999_999)
+        );
+        assert_eq!(max_joltage("", 0, 10), Ok(0));
+        assert_eq!(max_joltage("3A9F", 2, 16), Ok(0xAF));
+        assert_eq!(max_joltage(&"f".repeat(17), 16, 16), Ok(u64::MAX));
+        assert_eq!(
+            max_joltage(&"f".repeat(17), 17, 16),
+            Err(JoltageError::TooManyDigits { k: 17 })
+        );
+    }
+
+    #[test]
+    fn test_native_digits() {
+        assert_eq!(native_digits(10), 19);
+        assert_eq!(native_digits(16), 16);
+        assert_eq!(native_digits(2), 64);
+        assert_eq!(native_digits(36), 12);
+    }
+
+    #[test]
+    fn test_decimal_joltage() {
+        for bank in EXAMPLE {
+            for radix in [10, 16] {
+                for k in 0..=native_digits(radix).min(bank.len()) {
+                    assert_eq!(
+                        max_joltage_decimal(bank, k, radix),
+                        max_joltage(bank, k, radix).map(Decimal::from)
+                    );
+                }
+            }
+        }
+        assert_eq!(
+            max_joltage_decimal(&"f".repeat(20), 20, 16)
+                .unwrap()
+                .to_string(),
+            "1208925819614629174706175"
+        );
+        let bank = "9".repeat(25);
+        assert_eq!(
+            max_joltage_decimal(&bank, 20, 10).unwrap().to_string(),
+            "9".repeat(20)
+        );
+        let bank = format!("{}{}", EXAMPLE[2], EXAMPLE[3]);
+        assert_eq!(
+            max_joltage_decimal(&bank, 24, 10).unwrap().to_string(),
+            "444234278818181911112111"
+        );
+    }
+
+    #[test]
+    fn test_selection_and_render() {
+        let rendered: Vec<String> = EXAMPLE
+            .iter()
+            .map(|bank| {
+                render(
+                    bank,
+                    &select_batteries(bank, 12, 10).unwrap(),
+                    Highlight::Brackets,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rendered,
+            [
+                "[987654321111]111",
+                "[81111111111]111[9]",
+                "23[4]2[34234234278]",
+                "[8]1[8]1[8]1[911112111]",
+            ]
+        );
+        assert_eq!(select_batteries("818181911112111", 2, 10), Ok(vec![6, 11]));
+        assert_eq!(
+            render("1234", &[1, 2], Highlight::Color),
+            "1\x1b[1;32m23\x1b[0m4"
+        );
+        assert_eq!(render("1234", &[], Highlight::Brackets), "1234");
+    }
+}
@@ -26,61 +26,175 @@
 // There are many batteries in front of you. Find the maximum joltage possible from each bank; what is the total output joltage?
 //
 
-use std::fs;
+use day03::{
+    Bank, BankError, BankReader, Decimal, Highlight, JoltageError, load_input, max_joltage,
+    max_joltage_decimal, native_digits, render, select_batteries,
+};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufReader, IsTerminal};
 
-fn main() {
-    let file_path = "input.txt";
-    println!("Part 1: {}", solve_part1(file_path).unwrap());
-    println!("Part 2: {}", solve_part2(file_path).unwrap());
-}
+const USAGE: &str = "usage: main [--show] [--k K] [--test-input] [--stream]
 
-fn load_input(_input: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    let input = fs::read_to_string(_input).expect("Failed to read input file");
-    let lines: Vec<String> = input.lines().map(|line| line.trim().to_string()).collect();
+  --show         list every bank with the batteries turned on highlighted
+  --k K          turn on K batteries per bank instead of solving parts 1 and 2
+  --test-input   solve the example in test_input.txt and check the puzzle's totals
+  --stream       read each bank a byte at a time, for banks too long to hold in memory
 
-    Ok(lines)
-}
+A bank may start with a header such as `k=5 base=16:` giving its own number of batteries
+to turn on and the base of its labels; either can be left out.";
 
-fn solve_part1(input: &str) -> Result<i32, Box<dyn std::error::Error>> {
-    let banks = load_input(input)?;
-    let mut total_joltage = 0;
+// The example from the puzzle, with the totals it gives for parts 1 and 2
+const TEST_INPUT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test_input.txt");
+const TEST_ANSWERS: [u64; 2] = [357, 3121910778619];
 
-    for bank in banks {
-        let max_joltage = find_max_joltage(&bank);
-        total_joltage += max_joltage;
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        std::process::exit(1);
     }
-
-    Ok(total_joltage)
 }
 
-fn find_max_joltage(line: &str) -> i32 {
-    let digits: Vec<char> = line.chars().collect();
-
-    if digits.len() < 2 {
-        return 0;
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let mut file_path = "input.txt";
+    let mut test_input = false;
+    let mut show = false;
+    let mut stream = false;
+    let mut k = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--show" => show = true,
+            "--stream" => stream = true,
+            "--test-input" => {
+                file_path = TEST_INPUT;
+                test_input = true;
+            }
+            "--k" => {
+                let value = args.next().ok_or(format!("--k needs a value\n{USAGE}"))?;
+                k = Some(
+                    value
+                        .parse::<usize>()
+                        .map_err(|err| format!("--k: {err}"))?,
+                );
+            }
+            _ => return Err(format!("unknown argument {arg:?}\n{USAGE}").into()),
+        }
     }
 
-    // Track the best joltage found so far
-    let mut max_joltage = 0;
+    if show && stream {
+        return Err(
+            format!("--show needs whole banks and cannot be used with --stream\n{USAGE}").into(),
+        );
+    }
+    if test_input && k.is_some() {
+        return Err(format!(
+            "--test-input only knows the answers for parts 1 and 2 and cannot be used with --k\n{USAGE}"
+        )
+        .into());
+    }
 
-    // For each position, find the best digit that can come after it
-    for i in 0..(digits.len() - 1) {
-        // Find the largest digit that comes after position i
-        let mut max_after = '0';
-        for j in (i + 1)..digits.len() {
-            if digits[j] > max_after {
-                max_after = digits[j];
+    if let Some(k) = k {
+        if show {
+            show_banks(file_path, k)?;
+        }
+        println!("k={k}: {}", solve(file_path, k, stream)?);
+        return Ok(());
+    }
+    if show {
+        show_banks(file_path, 2)?;
+    }
+    let part1 = solve_part1(file_path, stream)?;
+    println!("Part 1: {part1}");
+    if show {
+        show_banks(file_path, 12)?;
+    }
+    let part2 = solve_part2(file_path, stream)?;
+    println!("Part 2: {part2}");
+
+    if test_input {
+        for (part, (answer, expected)) in [part1, part2].into_iter().zip(TEST_ANSWERS).enumerate() {
+            if answer != Decimal::from(expected) {
+                let part = part + 1;
+                return Err(
+                    format!("part {part} gave {answer} for the example, not {expected}").into(),
+                );
             }
         }
+        println!("Both parts match the example");
+    }
+    Ok(())
+}
 
-        // Form the joltage and update if it's better
-        let joltage = format!("{}{}", digits[i], max_after).parse().unwrap_or(0);
-        if joltage > max_joltage {
-            max_joltage = joltage;
+// Turns on `k` batteries in every bank whose header does not say otherwise. Joltages that fit
+// in a u64 are summed natively, and only the larger ones in a `Decimal`.
+fn solve(input: &str, k: usize, stream: bool) -> Result<Decimal, Box<dyn std::error::Error>> {
+    if stream {
+        return solve_streaming(input, k);
+    }
+    let mut native_total: u128 = 0;
+    let mut total_joltage = Decimal::default();
+    for bank in load_input(input)? {
+        let k = bank.k.unwrap_or(k);
+        if k <= native_digits(bank.radix) {
+            let joltage = max_joltage(&bank.batteries, k, bank.radix).map_err(at_line(&bank))?;
+            native_total += u128::from(joltage);
+        } else {
+            total_joltage += &bank_joltage(&bank, k).map_err(at_line(&bank))?;
         }
     }
+    total_joltage += &Decimal::from(native_total);
+    Ok(total_joltage)
+}
+
+// Like `solve`, but never holds more than O(k) of a bank in memory.
+fn solve_streaming(input: &str, k: usize) -> Result<Decimal, Box<dyn std::error::Error>> {
+    let mut reader = BankReader::new(BufReader::new(File::open(input)?));
+    let mut total_joltage = Decimal::default();
+    while let Some(bank) = reader.next_bank(k)? {
+        total_joltage += &bank.joltage();
+    }
+    Ok(total_joltage)
+}
+
+fn bank_joltage(bank: &Bank, k: usize) -> Result<Decimal, JoltageError> {
+    if k <= native_digits(bank.radix) {
+        max_joltage(&bank.batteries, k, bank.radix).map(Decimal::from)
+    } else {
+        max_joltage_decimal(&bank.batteries, k, bank.radix)
+    }
+}
+
+// Prefixes a bank's error with its line, as `BankReader` does when streaming.
+fn at_line(bank: &Bank) -> impl Fn(JoltageError) -> BankError {
+    let line = bank.line;
+    move |error| BankError { line, error }
+}
+
+// Prints each bank with its chosen batteries, so the greedy choices can be checked by eye.
+fn show_banks(input: &str, k: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let highlight = if io::stdout().is_terminal() {
+        Highlight::Color
+    } else {
+        Highlight::Brackets
+    };
+    for bank in load_input(input)? {
+        let k = bank.k.unwrap_or(k);
+        let indices = select_batteries(&bank.batteries, k, bank.radix).map_err(at_line(&bank))?;
+        let positions: Vec<String> = indices.iter().map(usize::to_string).collect();
+        println!(
+            "k={k} base={}  {}  {}  at {}",
+            bank.radix,
+            render(&bank.batteries, &indices, highlight),
+            bank_joltage(&bank, k).map_err(at_line(&bank))?,
+            positions.join(",")
+        );
+    }
+    Ok(())
+}
 
-    max_joltage
+fn solve_part1(input: &str, stream: bool) -> Result<Decimal, Box<dyn std::error::Error>> {
+    solve(input, 2, stream)
 }
 
 // --- Part Two ---
@@ -105,50 +219,6 @@ fn find_max_joltage(line: &str) -> i32 {
 // The total output joltage is now much larger: 987654321111 + 811111111119 + 434234234278 + 888911112111 = 3121910778619.
 //
 
-fn solve_part2(input: &str) -> Result<i64, Box<dyn std::error::Error>> {
-    let banks = load_input(input)?;
-    let mut total_joltage: i64 = 0;
-
-    for bank in banks {
-        let max_joltage = find_max_joltage_part2(&bank);
-        total_joltage += max_joltage;
-    }
-
-    Ok(total_joltage)
+fn solve_part2(input: &str, stream: bool) -> Result<Decimal, Box<dyn std::error::Error>> {
+    solve(input, 12, stream)
 }
-
-fn find_max_joltage_part2(line: &str) -> i64 {
-    let digits: Vec<char> = line.chars().collect();
-    let n = digits.len();
-
-    if n < 12 {
-        return 0;
-    }
-
-    // Find the lexicographically largest subsequence of length 12
-    let mut selected_digits = Vec::new();
-    let mut start_index = 0;
-
-    for i in 0..12 {
-        // For the i-th digit, we can only look up to position n - (12 - i) + 1
-        // because we need to leave enough digits for the remaining selections
-        let max_search_index = n - (12 - i);
-        
-        let mut max_digit = '0';
-        let mut max_index = start_index;
-
-        for j in start_index..=max_search_index {
-            if digits[j] > max_digit {
-                max_digit = digits[j];
-                max_index = j;
-            }
-        }
-
-        selected_digits.push(max_digit);
-        start_index = max_index + 1;
-    }
-
-    // Convert selected digits to an integer
-    let joltage_str: String = selected_digits.into_iter().collect();
-    joltage_str.parse().unwrap_or(0)
-}
\ No newline at end of file
@@ -0,0 +1,374 @@
+// Reading banks straight from a stream, so a bank of any length takes O(k) memory.
+
+use crate::input::{InputError, Reason, parse_header};
+use crate::{BankError, Decimal, JoltageError};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+// Past this, a line without a `:` yet is taken for a bad bank rather than a long header
+const MAX_HEADER: usize = 256;
+
+#[derive(Debug)]
+pub enum StreamError {
+    Io(io::Error),
+    Input(InputError),
+    /// A bank that has no joltage for its `k`.
+    Joltage(BankError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Io(error) => write!(f, "{error}"),
+            StreamError::Input(error) => write!(f, "{error}"),
+            StreamError::Joltage(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(error: io::Error) -> StreamError {
+        StreamError::Io(error)
+    }
+}
+
+/// The largest joltage of a bank that was read without being held in memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamedBank {
+    /// Batteries in the bank.
+    pub len: usize,
+    pub k: usize,
+    pub radix: u32,
+    /// Labels of the batteries turned on, most significant first.
+    pub digits: Vec<u8>,
+}
+
+impl StreamedBank {
+    pub fn joltage(&self) -> Decimal {
+        Decimal::from_digits(&self.digits, self.radix)
+    }
+}
+
+/// Reads banks one byte at a time in the format of [`parse_banks`](crate::parse_banks).
+///
+/// Only the header and O(k) batteries of a bank are kept, so a single bank may be far larger
+/// than memory. Error columns count bytes rather than characters, and a byte that is not
+/// ASCII is reported as U+FFFD.
+pub struct BankReader<R> {
+    reader: R,
+    line: usize,
+}
+
+enum Line {
+    Bank(StreamedBank),
+    Blank,
+    End,
+}
+
+impl<R: BufRead> BankReader<R> {
+    pub fn new(reader: R) -> BankReader<R> {
+        BankReader { reader, line: 0 }
+    }
+
+    /// The next bank with `k` batteries turned on, unless its header gives another `k`, or
+    /// `None` at the end of the input.
+    pub fn next_bank(&mut self, k: usize) -> Result<Option<StreamedBank>, StreamError> {
+        loop {
+            self.line += 1;
+            match self.read_line(k)? {
+                Line::Bank(bank) => return Ok(Some(bank)),
+                Line::Blank => continue,
+                Line::End => return Ok(None),
+            }
+        }
+    }
+
+    fn read_line(&mut self, k: usize) -> Result<Line, StreamError> {
+        let line = self.line;
+        let error = |column: usize, reason| {
+            StreamError::Input(InputError {
+                line,
+                column: column + 1,
+                reason,
+            })
+        };
+        let battery = |found: u8, radix| Reason::Battery {
+            found: if found.is_ascii() {
+                char::from(found)
+            } else {
+                char::REPLACEMENT_CHARACTER
+            },
+            radix,
+        };
+
+        let mut column = 0;
+        let mut header: Option<Vec<u8>> = None;
+        let mut selector: Option<Selector> = None;
+        let mut after_header = false;
+        let mut carriage_return = false;
+        let end_of_input = loop {
+            let Some(byte) = self.next_byte()? else {
+                break true;
+            };
+            let radix = selector.as_ref().map_or(10, |selector| selector.radix);
+            if carriage_return && byte != b'\n' {
+                return Err(error(column - 1, battery(b'\r', radix)));
+            }
+            match byte {
+                b'\n' => break false,
+                b'\r' => carriage_return = true,
+                // Until a header or a bank starts there is no selector
+                b':' if selector.is_none() => {
+                    let text = header.take().unwrap_or_default();
+                    let text = String::from_utf8_lossy(&text);
+                    let (header_k, radix) =
+                        parse_header(&text).map_err(|(at, reason)| error(at, reason))?;
+                    selector = Some(Selector::new(header_k.unwrap_or(k), radix));
+                    after_header = true;
+                }
+                _ if header.is_some() => {
+                    let text = header.as_mut().unwrap();
+                    if text.len() == MAX_HEADER {
+                        return Err(error(0, battery(text[0], radix)));
+                    }
+                    text.push(byte);
+                }
+                _ => match &mut selector {
+                    // A header can only be where a plain bank could not start
+                    None if !byte.is_ascii_digit() => header = Some(vec![byte]),
+                    None => {
+                        let mut plain = Selector::new(k, 10);
+                        plain.push(byte - b'0');
+                        selector = Some(plain);
+                    }
+                    Some(_) if after_header && byte.is_ascii_whitespace() => {}
+                    Some(selector) => {
+                        after_header = false;
+                        let digit = char::from(byte)
+                            .to_digit(radix)
+                            .filter(|_| byte.is_ascii())
+                            .ok_or_else(|| error(column, battery(byte, radix)))?;
+                        selector.push(digit as u8);
+                    }
+                },
+            }
+            column += 1;
+        };
+
+        if carriage_return && end_of_input {
+            let radix = selector.as_ref().map_or(10, |selector| selector.radix);
+            return Err(error(column - 1, battery(b'\r', radix)));
+        }
+        if let Some(text) = header {
+            return Err(error(0, battery(text[0], 10)));
+        }
+        match selector {
+            None if end_of_input => Ok(Line::End),
+            None => Ok(Line::Blank),
+            Some(selector) => {
+                let (len, k, radix) = (selector.len, selector.k, selector.radix);
+                let digits = selector
+                    .finish()
+                    .map_err(|error| StreamError::Joltage(BankError { line, error }))?;
+                Ok(Line::Bank(StreamedBank {
+                    len,
+                    k,
+                    radix,
+                    digits,
+                }))
+            }
+        }
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let byte = self.reader.fill_buf()?.first().copied();
+        if byte.is_some() {
+            self.reader.consume(1);
+        }
+        Ok(byte)
+    }
+}
+
+// The monotonic stack of `choose`, fed through a lookahead of the last k batteries so it never
+// needs the bank's length. A battery leaving the lookahead still has k more after it, so it may
+// replace smaller ones freely; only the final k are fed knowing exactly how many remain.
+struct Selector {
+    k: usize,
+    radix: u32,
+    len: usize,
+    stack: Vec<u8>,
+    lookahead: VecDeque<u8>,
+}
+
+impl Selector {
+    fn new(k: usize, radix: u32) -> Selector {
+        Selector {
+            k,
+            radix,
+            len: 0,
+            stack: Vec::new(),
+            lookahead: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, digit: u8) {
+        self.len += 1;
+        if self.lookahead.len() == self.k {
+            let Some(oldest) = self.lookahead.pop_front() else {
+                // With k = 0 nothing is ever turned on
+                return;
+            };
+            self.feed(oldest, self.k + 1);
+        }
+        self.lookahead.push_back(digit);
+    }
+
+    // `remaining` counts this battery and, at least, every one after it.
+    fn feed(&mut self, digit: u8, remaining: usize) {
+        while let Some(&top) = self.stack.last() {
+            if top >= digit || self.stack.len() - 1 + remaining < self.k {
+                break;
+            }
+            self.stack.pop();
+        }
+        if self.stack.len() < self.k {
+            self.stack.push(digit);
+        }
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>, JoltageError> {
+        if self.len < self.k {
+            return Err(JoltageError::TooShort {
+                len: self.len,
+                k: self.k,
+            });
+        }
+        let tail = std::mem::take(&mut self.lookahead);
+        let count = tail.len();
+        for (index, digit) in tail.into_iter().enumerate() {
+            self.feed(digit, count - index);
+        }
+        Ok(self.stack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::{max_joltage_decimal, parse_banks};
+    use std::io::BufReader;
+
+    // Every bank in `text`, read through a one-byte buffer so nothing relies on whole lines.
+    fn stream(text: &str, k: usize) -> Result<Vec<StreamedBank>, StreamError> {
+        let mut reader = BankReader::new(BufReader::with_capacity(1, text.as_bytes()));
+        let mut banks = Vec::new();
+        while let Some(bank) = reader.next_bank(k)? {
+            banks.push(bank);
+        }
+        Ok(banks)
+    }
+
+    #[test]
+    fn test_matches_whole_bank_solver() {
+        let mut rng = Rng::new(25);
+        for (radix, labels) in [
+            (10, "0123456789"),
+            (10, "129"),
+            (16, "0123456789abcdefABCDEF"),
+        ] {
+            for len in 0..40 {
+                let bank: String = (0..len)
+                    .map(|_| char::from(labels.as_bytes()[rng.below(labels.len() as u64) as usize]))
+                    .collect();
+                for k in 0..=len + 1 {
+                    let text = format!("base={radix}: {bank}\n");
+                    let streamed = stream(&text, k);
+                    match max_joltage_decimal(&bank, k, radix) {
+                        Ok(joltage) => {
+                            let streamed = streamed.unwrap();
+                            assert_eq!(streamed[0].joltage(), joltage, "{bank} k={k}");
+                            assert_eq!((streamed[0].len, streamed[0].digits.len()), (len, k));
+                        }
+                        Err(error) => assert!(
+                            matches!(streamed, Err(StreamError::Joltage(ref e)) if *e == BankError { line: 1, error }),
+                            "{bank} k={k}"
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_banks_as_parse_banks() {
+        let text = "987654321111111\r\n\n\nk=5 base=16: 3A9Fb0c\n  k=3 :987\n:12\n818181911112111";
+        let streamed = stream(text, 2).unwrap();
+        let parsed = parse_banks(text).unwrap();
+        assert_eq!(streamed.len(), parsed.len());
+        for (streamed, parsed) in streamed.iter().zip(&parsed) {
+            let k = parsed.k.unwrap_or(2);
+            assert_eq!((streamed.k, streamed.radix), (k, parsed.radix));
+            assert_eq!(
+                Ok(streamed.joltage()),
+                max_joltage_decimal(&parsed.batteries, k, parsed.radix)
+            );
+        }
+        assert_eq!(stream("", 2).unwrap(), []);
+        assert_eq!(stream("\n\n", 2).unwrap(), []);
+    }
+
+    #[test]
+    fn test_long_bank() {
+        let bank = crate::bench::Distribution::Uniform
+            .banks(200_000, 1, 3)
+            .remove(0);
+        for k in [1, 12, 1000] {
+            let streamed = stream(&bank, k).unwrap();
+            assert_eq!(Ok(streamed[0].joltage()), max_joltage_decimal(&bank, k, 10));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let input_error = |text, k| match stream(text, k) {
+            Err(StreamError::Input(error)) => error,
+            other => panic!("{text:?} gave {other:?}"),
+        };
+        let error = input_error("12345\n\n987a321\n", 2);
+        assert_eq!((error.line, error.column), (3, 4));
+        assert_eq!(
+            error.to_string(),
+            "line 3, column 4: expected a base 10 battery digit, found 'a'"
+        );
+        assert_eq!(input_error("12 34", 2).column, 3);
+        assert_eq!(input_error(" 1234", 2).column, 1);
+        assert_eq!(input_error("12\r34", 2).column, 3);
+        assert_eq!(input_error("1234\r", 2).column, 5);
+        assert_eq!(input_error("k=2 base=16: 12g", 2).column, 16);
+        assert_eq!(input_error("k=2  n=3: 12", 2).column, 6);
+        assert_eq!(
+            input_error("1\nk=2 k=3: 12", 1).reason,
+            Reason::Repeated("k".to_string())
+        );
+        let long = format!("x{}", "1".repeat(1000));
+        assert_eq!(input_error(&long, 2).column, 1);
+        assert!(matches!(
+            input_error("1\u{e9}", 1).reason,
+            Reason::Battery {
+                found: char::REPLACEMENT_CHARACTER,
+                ..
+            }
+        ));
+
+        let error = stream("98\n12345\n", 12).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1: cannot turn on 12 batteries in a bank of 2"
+        );
+    }
+}